@@ -0,0 +1,240 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor;
+use rustc_serialize::{Decodable, Decoder};
+use coin::{SafeCoin, Wallet};
+use data::{ImmutableData, ImmutableDataBackup, ImmutableDataSacrificial, StructuredData, DataMap};
+use id::{IdType, IdTypePublicPart, PublicIdType, RevocationIdType, RevocationIdTypePublicPart, SupersededRecord,
+         RevocationCertificate};
+use Error;
+
+/// AnyData holds any of the types tagged in `data_tags`, decoded according to its cbor tag
+///
+/// #Examples
+///
+/// ```
+/// extern crate cbor;
+/// extern crate maidsafe_types;
+/// use maidsafe_types::{AnyData, ImmutableData};
+///
+/// # fn main() {
+/// let immutable_data = ImmutableData::new("some value".to_string().into_bytes());
+/// let mut e = cbor::Encoder::from_memory();
+/// e.encode(&[&immutable_data]).unwrap();
+///
+/// match AnyData::from_bytes(e.as_bytes()).unwrap() {
+///     AnyData::ImmutableData(decoded) => assert_eq!(immutable_data, decoded),
+///     _ => panic!("Unexpected!"),
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub enum AnyData {
+    /// StructuredData
+    StructuredData(StructuredData),
+    /// ImmutableData
+    ImmutableData(ImmutableData),
+    /// ImmutableDataBackup
+    ImmutableDataBackup(ImmutableDataBackup),
+    /// ImmutableDataSacrificial
+    ImmutableDataSacrificial(ImmutableDataSacrificial),
+    /// Maid
+    Maid(IdType),
+    /// Mpid
+    Mpid(IdType),
+    /// AnMaid
+    AnMaid(RevocationIdType),
+    /// AnMpid
+    AnMpid(RevocationIdType),
+    /// PublicMaid
+    PublicMaid(PublicIdType),
+    /// PublicMpid
+    PublicMpid(PublicIdType),
+    /// SafeCoin
+    SafeCoin(SafeCoin),
+    /// DataMap
+    DataMap(DataMap),
+    /// SupersededRecord
+    SupersededRecord(SupersededRecord),
+    /// RevocationCertificate
+    RevocationCertificate(RevocationCertificate),
+    /// IdTypePublicPart
+    IdTypePublicPart(IdTypePublicPart),
+    /// Wallet
+    Wallet(Wallet),
+    /// RevocationIdTypePublicPart
+    RevocationIdTypePublicPart(RevocationIdTypePublicPart),
+}
+
+/// Returns true if the tag belongs to one of the types held by AnyData
+pub fn is_known_tag(tag: u64) -> bool {
+    match tag {
+        ::data_tags::IMMUTABLE_DATA_TAG |
+        ::data_tags::IMMUTABLE_DATA_BACKUP_TAG |
+        ::data_tags::IMMUTABLE_DATA_SACRIFICIAL_TAG |
+        ::data_tags::STRUCTURED_DATA_TAG |
+        ::data_tags::MAID_TAG |
+        ::data_tags::MPID_TAG |
+        ::data_tags::AN_MAID_TAG |
+        ::data_tags::AN_MPID_TAG |
+        ::data_tags::PUBLIC_MAID_TAG |
+        ::data_tags::PUBLIC_MPID_TAG |
+        ::data_tags::SAFECOIN_TAG |
+        ::data_tags::DATA_MAP_TAG |
+        ::data_tags::SUPERSEDED_ID_TAG |
+        ::data_tags::REVOCATION_CERTIFICATE_TAG |
+        ::data_tags::ID_PUBLIC_PART_TAG |
+        ::data_tags::WALLET_TAG |
+        ::data_tags::REVOCATION_ID_PUBLIC_PART_TAG => true,
+        _ => false,
+    }
+}
+
+/// Reads only the cbor tag of an encoded item
+struct TagPeek(u64);
+
+impl Decodable for TagPeek {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TagPeek, D::Error> {
+        Ok(TagPeek(try!(d.read_u64())))
+    }
+}
+
+/// Returns the cbor tag of the first item of the encoded bytes
+pub fn peek_tag(bytes: &[u8]) -> Result<u64, Error> {
+    let mut d = cbor::Decoder::from_bytes(bytes);
+    let TagPeek(tag) = try!(try!(d.decode().next().ok_or(cbor::CborError::UnexpectedEOF)));
+    Ok(tag)
+}
+
+impl AnyData {
+    /// Decodes the first item of the cbor encoded bytes, dispatching on its tag.  Items in an
+    /// envelope of any supported format version are accepted as well as bare ones.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AnyData, Error> {
+        let (_, payload) = try!(::envelope::open(bytes));
+        let tag = try!(peek_tag(&payload));
+        if !is_known_tag(tag) {
+            return Err(Error::UnknownTag(tag));
+        }
+        let mut d = cbor::Decoder::from_bytes(&payload[..]);
+        Ok(try!(try!(d.decode().next().ok_or(cbor::CborError::UnexpectedEOF))))
+    }
+
+    /// Returns the cbor tag of the held type
+    pub fn tag(&self) -> u64 {
+        match *self {
+            AnyData::StructuredData(_) => ::data_tags::STRUCTURED_DATA_TAG,
+            AnyData::ImmutableData(_) => ::data_tags::IMMUTABLE_DATA_TAG,
+            AnyData::ImmutableDataBackup(_) => ::data_tags::IMMUTABLE_DATA_BACKUP_TAG,
+            AnyData::ImmutableDataSacrificial(_) => ::data_tags::IMMUTABLE_DATA_SACRIFICIAL_TAG,
+            AnyData::Maid(_) => ::data_tags::MAID_TAG,
+            AnyData::Mpid(_) => ::data_tags::MPID_TAG,
+            AnyData::AnMaid(_) => ::data_tags::AN_MAID_TAG,
+            AnyData::AnMpid(_) => ::data_tags::AN_MPID_TAG,
+            AnyData::PublicMaid(_) => ::data_tags::PUBLIC_MAID_TAG,
+            AnyData::PublicMpid(_) => ::data_tags::PUBLIC_MPID_TAG,
+            AnyData::SafeCoin(_) => ::data_tags::SAFECOIN_TAG,
+            AnyData::DataMap(_) => ::data_tags::DATA_MAP_TAG,
+            AnyData::SupersededRecord(_) => ::data_tags::SUPERSEDED_ID_TAG,
+            AnyData::RevocationCertificate(_) => ::data_tags::REVOCATION_CERTIFICATE_TAG,
+            AnyData::IdTypePublicPart(_) => ::data_tags::ID_PUBLIC_PART_TAG,
+            AnyData::Wallet(_) => ::data_tags::WALLET_TAG,
+            AnyData::RevocationIdTypePublicPart(_) => ::data_tags::REVOCATION_ID_PUBLIC_PART_TAG,
+        }
+    }
+}
+
+impl Decodable for AnyData {
+    fn decode<D: Decoder>(d: &mut D) -> Result<AnyData, D::Error> {
+        let tag = try!(d.read_u64());
+
+        match tag {
+            ::data_tags::IMMUTABLE_DATA_TAG => Ok(AnyData::ImmutableData(try!(Decodable::decode(d)))),
+            ::data_tags::IMMUTABLE_DATA_BACKUP_TAG => Ok(AnyData::ImmutableDataBackup(try!(Decodable::decode(d)))),
+            ::data_tags::IMMUTABLE_DATA_SACRIFICIAL_TAG => Ok(AnyData::ImmutableDataSacrificial(try!(Decodable::decode(d)))),
+            ::data_tags::STRUCTURED_DATA_TAG => Ok(AnyData::StructuredData(try!(Decodable::decode(d)))),
+            ::data_tags::MAID_TAG => Ok(AnyData::Maid(try!(Decodable::decode(d)))),
+            ::data_tags::MPID_TAG => Ok(AnyData::Mpid(try!(Decodable::decode(d)))),
+            ::data_tags::AN_MAID_TAG => Ok(AnyData::AnMaid(try!(Decodable::decode(d)))),
+            ::data_tags::AN_MPID_TAG => Ok(AnyData::AnMpid(try!(Decodable::decode(d)))),
+            ::data_tags::PUBLIC_MAID_TAG => Ok(AnyData::PublicMaid(try!(Decodable::decode(d)))),
+            ::data_tags::PUBLIC_MPID_TAG => Ok(AnyData::PublicMpid(try!(Decodable::decode(d)))),
+            ::data_tags::SAFECOIN_TAG => Ok(AnyData::SafeCoin(try!(Decodable::decode(d)))),
+            ::data_tags::DATA_MAP_TAG => Ok(AnyData::DataMap(try!(Decodable::decode(d)))),
+            ::data_tags::SUPERSEDED_ID_TAG => Ok(AnyData::SupersededRecord(try!(Decodable::decode(d)))),
+            ::data_tags::REVOCATION_CERTIFICATE_TAG => Ok(AnyData::RevocationCertificate(try!(Decodable::decode(d)))),
+            ::data_tags::ID_PUBLIC_PART_TAG => Ok(AnyData::IdTypePublicPart(try!(Decodable::decode(d)))),
+            ::data_tags::WALLET_TAG => Ok(AnyData::Wallet(try!(Decodable::decode(d)))),
+            ::data_tags::REVOCATION_ID_PUBLIC_PART_TAG =>
+                Ok(AnyData::RevocationIdTypePublicPart(try!(Decodable::decode(d)))),
+            ::data_tags::ENVELOPE_TAG => {
+                let (version, payload) = try!(Decodable::decode(d));
+                let payload = try_decode!(d, ::envelope::upgrade_payload(version, payload));
+                Ok(try_decode!(d, AnyData::from_bytes(&payload)))
+            },
+            _ => Err(d.error(&Error::UnknownTag(tag).decode_description())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cbor;
+    use cbor::CborTagEncode;
+    use data::ImmutableData;
+    use coin::SafeCoin;
+    use Random;
+
+    #[test]
+    fn from_bytes() {
+        let immutable_data = ImmutableData::generate_random();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&immutable_data]).unwrap();
+
+        match AnyData::from_bytes(e.as_bytes()) {
+            Ok(AnyData::ImmutableData(decoded)) => assert_eq!(immutable_data, decoded),
+            _ => panic!("Unexpected!"),
+        }
+
+        let safecoin = SafeCoin::generate_random();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&safecoin]).unwrap();
+
+        let decoded = AnyData::from_bytes(e.as_bytes()).unwrap();
+        assert_eq!(decoded.tag(), ::data_tags::SAFECOIN_TAG);
+        match decoded {
+            AnyData::SafeCoin(decoded) => assert_eq!(safecoin, decoded),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn unknown_tag() {
+        let unknown_tag = ::MAIDSAFE_TAG + 99;
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(unknown_tag, &(vec![1u8, 2, 3]))]).unwrap();
+
+        match AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::UnknownTag(tag)) => assert_eq!(tag, unknown_tag),
+            _ => panic!("Unexpected!"),
+        }
+
+        let mut d = cbor::Decoder::from_bytes(e.as_bytes());
+        assert!(d.decode::<AnyData>().next().unwrap().is_err());
+    }
+}
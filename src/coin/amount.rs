@@ -0,0 +1,119 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::fmt;
+
+/// Number of nano units in one whole SafeCoin
+pub const NANO_PER_COIN: u64 = 1_000_000_000;
+
+/// Amount
+///
+/// A quantity of SafeCoin held as a whole number of nano units.  Arithmetic is checked, returning
+/// `Error::AmountOutOfRange` rather than wrapping.  At most 2^32 coins are ever minted, so any
+/// genuine total fits comfortably.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::coin::Amount;
+///
+/// let coin = Amount::from_coins(1).unwrap();
+/// let payment = Amount::from_nano(250_000_000);
+/// assert_eq!(coin.checked_sub(payment).unwrap().to_string(), "0.750000000".to_string());
+/// assert!(payment.checked_sub(coin).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Amount(u64);
+
+impl Amount {
+    /// The amount of nothing
+    pub fn zero() -> Amount {
+        Amount(0)
+    }
+    /// The value of a newly minted coin
+    pub fn coin() -> Amount {
+        Amount(NANO_PER_COIN)
+    }
+    /// Constructs an amount of nano units
+    pub fn from_nano(nano: u64) -> Amount {
+        Amount(nano)
+    }
+    /// Constructs an amount of whole coins
+    pub fn from_coins(coins: u64) -> Result<Amount, ::Error> {
+        coins.checked_mul(NANO_PER_COIN).map(Amount).ok_or(::Error::AmountOutOfRange)
+    }
+    /// Returns the number of nano units
+    pub fn as_nano(&self) -> u64 {
+        self.0
+    }
+    /// Returns true if the amount is zero
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+    /// Adds other, failing on overflow
+    pub fn checked_add(self, other: Amount) -> Result<Amount, ::Error> {
+        self.0.checked_add(other.0).map(Amount).ok_or(::Error::AmountOutOfRange)
+    }
+    /// Subtracts other, failing if it is larger
+    pub fn checked_sub(self, other: Amount) -> Result<Amount, ::Error> {
+        self.0.checked_sub(other.0).map(Amount).ok_or(::Error::AmountOutOfRange)
+    }
+    /// Adds up amounts, failing on overflow
+    pub fn checked_sum<I: Iterator<Item=Amount>>(amounts: I) -> Result<Amount, ::Error> {
+        let mut total = Amount::zero();
+        for amount in amounts {
+            total = try!(total.checked_add(amount));
+        }
+        Ok(total)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:09}", self.0 / NANO_PER_COIN, self.0 % NANO_PER_COIN)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::u64;
+
+    #[test]
+    fn checked_arithmetic() {
+        let coin = Amount::coin();
+        assert_eq!(Amount::from_coins(3).unwrap().as_nano(), 3 * NANO_PER_COIN);
+        assert!(Amount::from_coins(u64::MAX / NANO_PER_COIN + 1).is_err());
+
+        let half = Amount::from_nano(NANO_PER_COIN / 2);
+        assert_eq!(half.checked_add(half).unwrap(), coin);
+        assert_eq!(coin.checked_sub(half).unwrap(), half);
+        assert!(coin.checked_sub(coin).unwrap().is_zero());
+        assert!(half.checked_sub(coin).is_err());
+        assert!(Amount::from_nano(u64::MAX).checked_add(Amount::from_nano(1)).is_err());
+
+        assert_eq!(Amount::checked_sum(vec![half, half, coin].into_iter()).unwrap(), Amount::from_coins(2).unwrap());
+        assert!(Amount::checked_sum(vec![Amount::from_nano(u64::MAX), half].into_iter()).is_err());
+        assert!(half < coin);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Amount::from_nano(1_500_000_001).to_string(), "1.500000001".to_string());
+        assert_eq!(Amount::zero().to_string(), "0.000000000".to_string());
+    }
+}
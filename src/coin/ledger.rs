@@ -0,0 +1,425 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor;
+use helper::*;
+use routing::NameType;
+use routing::sendable::Sendable;
+use sodiumoxide::crypto;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use super::amount::*;
+use super::safecoin::*;
+
+/// Ledger
+///
+/// Records every SafeCoin which has been minted or split off and each of its transfers, standing
+/// in for the vaults when testing client code.  Every state a coin has been in is kept, which
+/// serves as its audit trail.  A coin which has been split is spent and cannot be transferred.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags, SafeCoin};
+/// use maidsafe_types::coin::Ledger;
+///
+/// let payer = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
+/// let payee = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
+/// let coin = SafeCoin::new(payer.name(), vec![payer.public_keys().0]);
+///
+/// let mut ledger = Ledger::new();
+/// ledger.mint(coin.clone()).unwrap();
+/// ledger.transfer(coin.transfer(vec![payee.public_keys().0], &[&payer]).unwrap()).unwrap();
+/// assert_eq!(ledger.audit_trail(&payer.name()).unwrap().len(), 2);
+/// ```
+pub struct Ledger {
+    coins: HashMap<Vec<u8>, Vec<SafeCoin>>,
+    spent: HashMap<Vec<u8>, Split>,
+    // Names in the order the coins were recorded, so a saved ledger replays parents before outputs
+    order: Vec<Vec<u8>>,
+}
+
+impl Ledger {
+    /// Constructs an empty ledger
+    pub fn new() -> Ledger {
+        Ledger { coins: HashMap::new(), spent: HashMap::new(), order: Vec::new() }
+    }
+
+    /// Returns the number of coins in the ledger
+    pub fn len(&self) -> usize {
+        self.coins.len()
+    }
+
+    /// Records a newly minted coin, which is worth one whole coin
+    pub fn mint(&mut self, coin: SafeCoin) -> Result<(), ::Error> {
+        if coin.version() != 0 {
+            return Err(::Error::InvalidVersion { expected: 0, actual: coin.version() });
+        }
+        if !coin.previous_owners().is_empty() || coin.split().is_some() {
+            return Err(::Error::NotNewlyMinted);
+        }
+        if coin.value() != Amount::coin() {
+            return Err(::Error::ValueNotConserved);
+        }
+        let key = coin.name().0.to_vec();
+        if self.coins.contains_key(&key) {
+            return Err(::Error::CoinAlreadyMinted(coin.name()));
+        }
+        let _ = self.coins.insert(key.clone(), vec![coin]);
+        self.order.push(key);
+        Ok(())
+    }
+
+    /// Records a transfer of a coin in the ledger, or a coin split off another.  The transfer must
+    /// be signed by, and only by, the coin's current owners.  Resubmitting a recorded transfer has
+    /// no effect, while a different transfer of the same version of the coin, or any transfer of
+    /// a coin which has been split, is a double spend.
+    pub fn transfer(&mut self, coin: SafeCoin) -> Result<(), ::Error> {
+        if coin.version() == 0 && coin.split().is_some() {
+            return self.record_split(coin);
+        }
+        let name = coin.name();
+        let spent = self.is_spent(&name);
+        let history = match self.coins.get_mut(&name.0.to_vec()) {
+            Some(history) => history,
+            None => return Err(::Error::UnknownCoin(name)),
+        };
+        try!(coin.validate());
+
+        let current_version = history[history.len() - 1].version();
+        if coin.version() <= current_version {
+            if history[coin.version() as usize] == coin {
+                return Ok(());
+            }
+            return Err(::Error::DoubleSpend(name));
+        }
+        if spent {
+            return Err(::Error::DoubleSpend(name));
+        }
+        if coin.version() != current_version + 1 {
+            return Err(::Error::InvalidVersion { expected: current_version + 1, actual: coin.version() });
+        }
+        if !keys_equal(coin.previous_owners(), history[history.len() - 1].owners()) {
+            return Err(::Error::NotOwner);
+        }
+        history.push(coin);
+        Ok(())
+    }
+
+    // Records an output of a split, spending its parent.  Further outputs of the same split are
+    // accepted, but any other split or transfer of the parent is a double spend.
+    fn record_split(&mut self, coin: SafeCoin) -> Result<(), ::Error> {
+        try!(coin.validate());
+        let split = match coin.split() {
+            Some(split) => split.clone(),
+            None => return Err(::Error::ValueNotConserved),
+        };
+        let parent_key = split.parent().0.to_vec();
+        match self.spent.get(&parent_key) {
+            Some(recorded) => {
+                if *recorded != split {
+                    return Err(::Error::DoubleSpend(split.parent().clone()));
+                }
+            },
+            None => {
+                let parent = match self.coins.get(&parent_key).and_then(|history| history.last()) {
+                    Some(parent) => parent,
+                    None => return Err(::Error::UnknownCoin(split.parent().clone())),
+                };
+                if parent.version() > split.parent_version() {
+                    return Err(::Error::DoubleSpend(split.parent().clone()));
+                }
+                if parent.version() != split.parent_version() {
+                    return Err(::Error::InvalidVersion { expected: parent.version(),
+                                                         actual: split.parent_version() });
+                }
+                if !keys_equal(coin.previous_owners(), parent.owners()) {
+                    return Err(::Error::NotOwner);
+                }
+                if parent.value() != split.parent_value() {
+                    return Err(::Error::ValueNotConserved);
+                }
+            },
+        }
+
+        let key = coin.name().0.to_vec();
+        if let Some(history) = self.coins.get(&key) {
+            if history[0] == coin {
+                return Ok(());
+            }
+            return Err(::Error::DoubleSpend(coin.name()));
+        }
+        let _ = self.spent.insert(parent_key, split);
+        let _ = self.coins.insert(key.clone(), vec![coin]);
+        self.order.push(key);
+        Ok(())
+    }
+
+    /// Returns true if the named coin has been split, so can no longer be transferred
+    pub fn is_spent(&self, name: &NameType) -> bool {
+        self.spent.contains_key(&name.0.to_vec())
+    }
+
+    /// Returns the current state of the named coin
+    pub fn current(&self, name: &NameType) -> Option<&SafeCoin> {
+        self.coins.get(&name.0.to_vec()).and_then(|history| history.last())
+    }
+
+    /// Returns every state the named coin has been in, starting from when it was minted
+    pub fn audit_trail(&self, name: &NameType) -> Option<&[SafeCoin]> {
+        self.coins.get(&name.0.to_vec()).map(|history| &history[..])
+    }
+
+    /// Returns the current state of each unspent coin owned solely by owner
+    pub fn coins_owned_by(&self, owner: &crypto::sign::PublicKey) -> Vec<&SafeCoin> {
+        self.coins.iter()
+                  .filter(|&(key, _)| !self.spent.contains_key(key))
+                  .filter_map(|(_, history)| history.last())
+                  .filter(|coin| coin.owners().len() == 1 && slice_equal(&coin.owners()[0].0, &owner.0))
+                  .collect()
+    }
+
+    /// Writes the full history of every coin to path
+    pub fn save(&self, path: &Path) -> Result<(), ::Error> {
+        let mut e = cbor::Encoder::from_memory();
+        for key in &self.order {
+            for coin in &self.coins[key] {
+                try!(e.encode(&[coin]));
+            }
+        }
+        let mut file = try!(File::create(path));
+        try!(file.write_all(e.as_bytes()));
+        Ok(())
+    }
+
+    /// Reads a ledger written by `save`, checking every transfer again as it is replayed
+    pub fn load(path: &Path) -> Result<Ledger, ::Error> {
+        let mut bytes = Vec::new();
+        let _ = try!(try!(File::open(path)).read_to_end(&mut bytes));
+        let mut ledger = Ledger::new();
+        let mut d = cbor::Decoder::from_bytes(&bytes[..]);
+        for item in d.decode::<::AnyData>() {
+            match try!(item) {
+                ::AnyData::SafeCoin(coin) => {
+                    if coin.version() == 0 && coin.split().is_none() {
+                        try!(ledger.mint(coin));
+                    } else {
+                        try!(ledger.transfer(coin));
+                    }
+                },
+                _ => return Err(::Error::CorruptedData),
+            }
+        }
+        Ok(ledger)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate rand;
+
+    use super::*;
+    use coin::{Amount, SafeCoin};
+    use id::IdType;
+    use routing::NameType;
+    use routing::sendable::Sendable;
+    use routing::types::{vector_as_u8_64_array, generate_random_vec_u8};
+    use Random;
+
+    fn random_name() -> NameType {
+        NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64)))
+    }
+
+    #[test]
+    fn mint_and_transfer() {
+        let payer = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let coin = SafeCoin::new(random_name(), vec![payer.public_keys().0]);
+        let mut ledger = Ledger::new();
+
+        let transferred = coin.transfer(vec![payee.public_keys().0], &[&payer]).unwrap();
+        match ledger.transfer(transferred.clone()) {
+            Err(::Error::UnknownCoin(_)) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match ledger.mint(transferred.clone()) {
+            Err(::Error::InvalidVersion { expected, actual }) => {
+                assert_eq!(expected, 0);
+                assert_eq!(actual, 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        ledger.mint(coin.clone()).unwrap();
+        match ledger.mint(coin.clone()) {
+            Err(::Error::CoinAlreadyMinted(_)) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert_eq!(ledger.coins_owned_by(&payer.public_keys().0).len(), 1);
+
+        ledger.transfer(transferred.clone()).unwrap();
+        // Resubmission is harmless
+        ledger.transfer(transferred.clone()).unwrap();
+        assert_eq!(ledger.current(&coin.name()), Some(&transferred));
+        assert_eq!(ledger.audit_trail(&coin.name()).unwrap(), &[coin.clone(), transferred.clone()][..]);
+        assert!(ledger.coins_owned_by(&payer.public_keys().0).is_empty());
+        assert_eq!(ledger.coins_owned_by(&payee.public_keys().0).len(), 1);
+
+        // Skipping a transfer
+        let skipped = transferred.transfer(vec![payer.public_keys().0], &[&payee]).unwrap()
+                                 .transfer(vec![payee.public_keys().0], &[&payer]).unwrap();
+        match ledger.transfer(skipped) {
+            Err(::Error::InvalidVersion { expected: 2, actual: 3 }) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn not_owner() {
+        let owner = IdType::generate_random();
+        let thief = IdType::generate_random();
+        let name = random_name();
+        let mut ledger = Ledger::new();
+        ledger.mint(SafeCoin::new(name.clone(), vec![owner.public_keys().0])).unwrap();
+
+        // A validly signed transfer of a coin the thief never owned
+        let forged = SafeCoin::new(name.clone(), vec![thief.public_keys().0])
+                         .transfer(vec![thief.public_keys().0], &[&thief]).unwrap();
+        assert!(forged.validate().is_ok());
+        match ledger.transfer(forged) {
+            Err(::Error::NotOwner) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert_eq!(ledger.audit_trail(&name).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn double_spend() {
+        let owner = IdType::generate_random();
+        let first_payee = IdType::generate_random();
+        let second_payee = IdType::generate_random();
+        let coin = SafeCoin::new(random_name(), vec![owner.public_keys().0]);
+        let mut ledger = Ledger::new();
+        ledger.mint(coin.clone()).unwrap();
+
+        let first = coin.transfer(vec![first_payee.public_keys().0], &[&owner]).unwrap();
+        let second = coin.transfer(vec![second_payee.public_keys().0], &[&owner]).unwrap();
+        ledger.transfer(first.clone()).unwrap();
+        match ledger.transfer(second) {
+            Err(::Error::DoubleSpend(name)) => assert_eq!(name, coin.name()),
+            _ => panic!("Unexpected!"),
+        }
+        assert_eq!(ledger.current(&coin.name()), Some(&first));
+    }
+
+    #[test]
+    fn split() {
+        let owner = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let coin = SafeCoin::new(random_name(), vec![owner.public_keys().0]);
+        let mut ledger = Ledger::new();
+        let price = Amount::from_nano(400_000_000);
+
+        let (payment, change) = coin.split_payment(vec![payee.public_keys().0], price, &[&owner]).unwrap();
+        match ledger.transfer(payment.clone()) {
+            Err(::Error::UnknownCoin(name)) => assert_eq!(name, coin.name()),
+            _ => panic!("Unexpected!"),
+        }
+        match ledger.mint(payment.clone()) {
+            Err(::Error::NotNewlyMinted) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        ledger.mint(coin.clone()).unwrap();
+        ledger.transfer(payment.clone()).unwrap();
+        ledger.transfer(payment.clone()).unwrap();
+        ledger.transfer(change.clone()).unwrap();
+        assert!(ledger.is_spent(&coin.name()));
+        assert_eq!(ledger.len(), 3);
+        assert_eq!(ledger.coins_owned_by(&owner.public_keys().0), vec![&change]);
+        assert_eq!(ledger.coins_owned_by(&payee.public_keys().0), vec![&payment]);
+
+        // The spent coin can be neither transferred nor split again
+        match ledger.transfer(coin.transfer(vec![payee.public_keys().0], &[&owner]).unwrap()) {
+            Err(::Error::DoubleSpend(name)) => assert_eq!(name, coin.name()),
+            _ => panic!("Unexpected!"),
+        }
+        let (other_payment, _) = coin.split_payment(vec![payee.public_keys().0], Amount::from_nano(1),
+                                                    &[&owner]).unwrap();
+        match ledger.transfer(other_payment) {
+            Err(::Error::DoubleSpend(name)) => assert_eq!(name, coin.name()),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Outputs are transferred like any other coin
+        ledger.transfer(payment.transfer(vec![owner.public_keys().0], &[&payee]).unwrap()).unwrap();
+        assert_eq!(ledger.coins_owned_by(&owner.public_keys().0).len(), 2);
+    }
+
+    #[test]
+    fn split_after_transfer() {
+        let owner = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let coin = SafeCoin::new(random_name(), vec![owner.public_keys().0]);
+        let mut ledger = Ledger::new();
+        ledger.mint(coin.clone()).unwrap();
+        ledger.transfer(coin.transfer(vec![payee.public_keys().0], &[&owner]).unwrap()).unwrap();
+
+        // The owner splits the coin they have already paid away
+        let (stale, _) = coin.split_payment(vec![owner.public_keys().0], Amount::from_nano(1), &[&owner]).unwrap();
+        match ledger.transfer(stale) {
+            Err(::Error::DoubleSpend(name)) => assert_eq!(name, coin.name()),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(!ledger.is_spent(&coin.name()));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let owner = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let mut ledger = Ledger::new();
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let coin = SafeCoin::new(random_name(), vec![owner.public_keys().0]);
+            names.push(coin.name());
+            ledger.mint(coin.clone()).unwrap();
+            ledger.transfer(coin.transfer(vec![payee.public_keys().0], &[&owner]).unwrap()).unwrap();
+        }
+        let current = ledger.current(&names[0]).unwrap().clone();
+        let (payment, change) = current.split_payment(vec![owner.public_keys().0], Amount::from_nano(7),
+                                                      &[&payee]).unwrap();
+        for output in vec![payment, change] {
+            names.push(output.name());
+            ledger.transfer(output).unwrap();
+        }
+
+        let path = ::std::env::temp_dir().join(format!("ledger_{}", rand::random::<u64>()));
+        ledger.save(&path).unwrap();
+        let loaded = Ledger::load(&path).unwrap();
+        let _ = ::std::fs::remove_file(&path);
+
+        assert_eq!(loaded.len(), 5);
+        assert!(loaded.is_spent(&names[0]));
+        for name in &names {
+            assert_eq!(loaded.audit_trail(name), ledger.audit_trail(name));
+        }
+        assert!(Ledger::load(&path).is_err());
+    }
+}
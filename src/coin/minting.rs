@@ -0,0 +1,361 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use data::ImmutableData;
+use helper::*;
+use routing::NameType;
+use routing::sendable::Sendable;
+use sodiumoxide::crypto;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use super::safecoin::*;
+
+/// Number of random bytes in the nonce of a FarmingChallenge
+pub const FARMING_NONCE_BYTES: usize = 32;
+
+/// FarmingChallenge
+///
+/// A single farming attempt issued by the CoinSpace to a farmer for one chunk.  The nonce is chosen
+/// by the coin space, not the farmer, so a farmer cannot try nonces until one wins, and the proof
+/// can only be computed once the challenge is issued, by a vault which holds the chunk at that time.
+#[derive(Clone)]
+pub struct FarmingChallenge {
+    farmer: crypto::sign::PublicKey,
+    chunk_name: NameType,
+    nonce: Vec<u8>,
+}
+
+impl FarmingChallenge {
+    /// Returns the signing key of the farmer the challenge was issued to
+    pub fn farmer(&self) -> &crypto::sign::PublicKey {
+        &self.farmer
+    }
+    /// Returns the name of the chunk the farmer must prove it holds
+    pub fn chunk_name(&self) -> &NameType {
+        &self.chunk_name
+    }
+    /// Returns the nonce chosen by the coin space
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+}
+
+impl PartialEq for FarmingChallenge {
+    fn eq(&self, other: &FarmingChallenge) -> bool {
+        slice_equal(&self.farmer.0, &other.farmer.0) &&
+        self.chunk_name == other.chunk_name &&
+        self.nonce == other.nonce
+    }
+}
+
+impl fmt::Debug for FarmingChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FarmingChallenge {{ farmer:{:?}, chunk_name:{:?}, nonce:{:?} }}",
+            self.farmer.0.to_vec(), self.chunk_name, self.nonce)
+    }
+}
+
+/// FarmingProof
+///
+/// A vault's answer to a FarmingChallenge.  The proof is the hash of the content of the challenged
+/// chunk together with the farmer's key and the challenge nonce, so it can only be made by holding
+/// the chunk and cannot be claimed by another farmer.  The name of the coin is derived from it.
+#[derive(Clone)]
+pub struct FarmingProof {
+    challenge: FarmingChallenge,
+    proof: Vec<u8>,
+}
+
+fn compute_proof(farmer: &crypto::sign::PublicKey, chunk: &ImmutableData, nonce: &[u8]) -> Vec<u8> {
+    let mut combined = chunk.value().clone();
+    combined.extend(farmer.0.iter().cloned());
+    combined.extend(nonce.iter().cloned());
+    crypto::hash::sha512::hash(&combined).0.to_vec()
+}
+
+impl FarmingProof {
+    /// Answers challenge from chunk, which must be the chunk it names
+    pub fn new(challenge: &FarmingChallenge, chunk: &ImmutableData) -> FarmingProof {
+        FarmingProof {
+            proof: compute_proof(&challenge.farmer, chunk, &challenge.nonce),
+            challenge: challenge.clone(),
+        }
+    }
+
+    /// Returns the challenge the proof answers
+    pub fn challenge(&self) -> &FarmingChallenge {
+        &self.challenge
+    }
+    /// Returns the signing key of the farmer, who owns any coin minted
+    pub fn farmer(&self) -> &crypto::sign::PublicKey {
+        &self.challenge.farmer
+    }
+    /// Returns the name of the chunk
+    pub fn chunk_name(&self) -> &NameType {
+        &self.challenge.chunk_name
+    }
+
+    /// Returns the name of the coin the attempt would mint
+    pub fn coin_name(&self) -> NameType {
+        let mut combined = self.challenge.chunk_name.0.to_vec();
+        combined.extend(self.proof.iter().cloned());
+        NameType(crypto::hash::sha512::hash(&combined).0)
+    }
+
+    /// Returns true if the proof was made from chunk
+    pub fn verify(&self, chunk: &ImmutableData) -> bool {
+        chunk.name() == self.challenge.chunk_name &&
+        slice_equal(&compute_proof(&self.challenge.farmer, chunk, &self.challenge.nonce), &self.proof)
+    }
+}
+
+impl PartialEq for FarmingProof {
+    fn eq(&self, other: &FarmingProof) -> bool {
+        self.challenge == other.challenge && self.proof == other.proof
+    }
+}
+
+impl fmt::Debug for FarmingProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FarmingProof {{ challenge:{:?}, proof:{:?} }}", self.challenge, self.proof)
+    }
+}
+
+/// CoinSpace
+///
+/// The names of the coins minted so far, the farming challenges outstanding and the farming rate,
+/// which is the chance in `u64::MAX` that a farming attempt succeeds.  An attempt succeeds when the
+/// first eight bytes of its coin name, read as a big-endian number, are below the farming rate.
+/// Each farmer has at most one challenge outstanding, and it is used up by the first attempt to
+/// mint with it, successful or not, so every attempt costs the farmer a request to the network.
+pub struct CoinSpace {
+    farming_rate: u64,
+    allocated: HashSet<Vec<u8>>,
+    challenges: HashMap<Vec<u8>, FarmingChallenge>,
+}
+
+impl CoinSpace {
+    /// Constructs a coin space with no coins allocated
+    pub fn new(farming_rate: u64) -> CoinSpace {
+        CoinSpace { farming_rate: farming_rate, allocated: HashSet::new(), challenges: HashMap::new() }
+    }
+
+    /// Returns the farming rate
+    pub fn farming_rate(&self) -> u64 {
+        self.farming_rate
+    }
+    /// Sets the farming rate, which the network adjusts as the supply of coins changes
+    pub fn set_farming_rate(&mut self, farming_rate: u64) {
+        self.farming_rate = farming_rate;
+    }
+    /// Returns the number of coins allocated
+    pub fn allocated_count(&self) -> usize {
+        self.allocated.len()
+    }
+    /// Returns true if a coin with the given name has been minted
+    pub fn is_allocated(&self, name: &NameType) -> bool {
+        self.allocated.contains(&name.0.to_vec())
+    }
+    /// Records a coin minted elsewhere, returning false if it was already allocated
+    pub fn allocate(&mut self, name: &NameType) -> bool {
+        self.allocated.insert(name.0.to_vec())
+    }
+
+    /// Issues farmer a challenge with a fresh random nonce to prove it holds the named chunk.  Any
+    /// challenge previously outstanding for the farmer is withdrawn.
+    pub fn issue_challenge(&mut self, farmer: &crypto::sign::PublicKey, chunk_name: &NameType)
+            -> FarmingChallenge {
+        let challenge = FarmingChallenge { farmer: farmer.clone(),
+                                           chunk_name: chunk_name.clone(),
+                                           nonce: crypto::randombytes::randombytes(FARMING_NONCE_BYTES),
+                                         };
+        let _ = self.challenges.insert(farmer.0.to_vec(), challenge.clone());
+        challenge
+    }
+    /// Returns true if challenge is outstanding
+    pub fn is_outstanding(&self, challenge: &FarmingChallenge) -> bool {
+        self.challenges.get(&challenge.farmer.0.to_vec()).map_or(false, |outstanding| outstanding == challenge)
+    }
+
+    /// Checks a farming attempt without minting or using up its challenge, returning the name of
+    /// the coin it earns
+    pub fn verify(&self, proof: &FarmingProof, chunk: &ImmutableData) -> Result<NameType, ::Error> {
+        if !self.is_outstanding(proof.challenge()) {
+            return Err(::Error::UnknownFarmingChallenge);
+        }
+        self.check_attempt(proof, chunk)
+    }
+
+    /// Uses up the challenge of a farming attempt and mints the coin it earns, owned by the farmer
+    pub fn mint(&mut self, proof: &FarmingProof, chunk: &ImmutableData) -> Result<SafeCoin, ::Error> {
+        if !self.is_outstanding(proof.challenge()) {
+            return Err(::Error::UnknownFarmingChallenge);
+        }
+        let _ = self.challenges.remove(&proof.farmer().0.to_vec());
+        let name = try!(self.check_attempt(proof, chunk));
+        let _ = self.allocate(&name);
+        Ok(SafeCoin::new(name, vec![proof.farmer().clone()]))
+    }
+
+    fn check_attempt(&self, proof: &FarmingProof, chunk: &ImmutableData) -> Result<NameType, ::Error> {
+        if !proof.verify(chunk) {
+            return Err(::Error::InvalidFarmingProof);
+        }
+        let name = proof.coin_name();
+        let position = name.0.iter().take(8).fold(0u64, |position, byte| (position << 8) | *byte as u64);
+        if position >= self.farming_rate {
+            return Err(::Error::FarmingAttemptFailed);
+        }
+        if self.is_allocated(&name) {
+            return Err(::Error::CoinAlreadyMinted(name));
+        }
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data::ImmutableData;
+    use id::IdType;
+    use routing::sendable::Sendable;
+    use std::u64;
+    use Random;
+
+    #[test]
+    fn farming_proof() {
+        let farmer = IdType::generate_random().public_keys().0;
+        let chunk = ImmutableData::new(vec![1u8; 1024]);
+        let challenge = FarmingChallenge { farmer: farmer.clone(), chunk_name: chunk.name(),
+                                           nonce: vec![2u8; FARMING_NONCE_BYTES] };
+        let proof = FarmingProof::new(&challenge, &chunk);
+
+        // Deterministic for the same challenge
+        let same = FarmingProof::new(&challenge.clone(), &chunk);
+        assert_eq!(proof, same);
+        assert_eq!(proof.coin_name(), same.coin_name());
+        assert!(proof.verify(&chunk));
+        assert!(!proof.verify(&ImmutableData::new(vec![3u8; 1024])));
+
+        // The nonce and farmer each change the coin
+        let other_nonce = FarmingChallenge { nonce: vec![3u8; FARMING_NONCE_BYTES], ..challenge.clone() };
+        assert!(FarmingProof::new(&other_nonce, &chunk).coin_name() != proof.coin_name());
+        let other_farmer = IdType::generate_random().public_keys().0;
+        let mut stolen = proof.clone();
+        stolen.challenge.farmer = other_farmer;
+        assert!(!stolen.verify(&chunk));
+    }
+
+    #[test]
+    fn mint() {
+        let farmer = IdType::generate_random().public_keys().0;
+        let chunk = ImmutableData::new(vec![1u8; 1024]);
+
+        let mut coin_space = CoinSpace::new(0);
+        let proof = FarmingProof::new(&coin_space.issue_challenge(&farmer, &chunk.name()), &chunk);
+        match coin_space.mint(&proof, &chunk) {
+            Err(::Error::FarmingAttemptFailed) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let mut coin_space = CoinSpace::new(u64::MAX);
+        let wrong_chunk = ImmutableData::new(vec![2u8; 1024]);
+        let proof = FarmingProof::new(&coin_space.issue_challenge(&farmer, &wrong_chunk.name()), &chunk);
+        match coin_space.mint(&proof, &wrong_chunk) {
+            Err(::Error::InvalidFarmingProof) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let proof = FarmingProof::new(&coin_space.issue_challenge(&farmer, &chunk.name()), &chunk);
+        let name = coin_space.verify(&proof, &chunk).unwrap();
+        assert_eq!(coin_space.allocated_count(), 0);
+        assert!(coin_space.is_outstanding(proof.challenge()));
+
+        let coin = coin_space.mint(&proof, &chunk).unwrap();
+        assert_eq!(coin.name(), name);
+        assert_eq!(coin.owners()[0].0, farmer.0);
+        assert!(coin.validate().is_ok());
+        assert!(coin_space.is_allocated(&name));
+        assert!(!coin_space.is_outstanding(proof.challenge()));
+        match coin_space.mint(&proof, &chunk) {
+            Err(::Error::UnknownFarmingChallenge) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn challenges() {
+        let farmer = IdType::generate_random().public_keys().0;
+        let chunk = ImmutableData::new(vec![1u8; 1024]);
+        let mut coin_space = CoinSpace::new(u64::MAX);
+
+        // A nonce chosen by the farmer is not accepted
+        let chosen = FarmingChallenge { farmer: farmer.clone(), chunk_name: chunk.name(),
+                                        nonce: vec![2u8; FARMING_NONCE_BYTES] };
+        match coin_space.verify(&FarmingProof::new(&chosen, &chunk), &chunk) {
+            Err(::Error::UnknownFarmingChallenge) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Nor is a challenge issued to another farmer
+        let first = coin_space.issue_challenge(&farmer, &chunk.name());
+        let other_farmer = IdType::generate_random().public_keys().0;
+        let mut stolen = FarmingProof::new(&first, &chunk);
+        stolen.challenge.farmer = other_farmer.clone();
+        stolen.proof = compute_proof(&other_farmer, &chunk, first.nonce());
+        assert!(stolen.verify(&chunk));
+        match coin_space.mint(&stolen, &chunk) {
+            Err(::Error::UnknownFarmingChallenge) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Each farmer holds one challenge at a time, so challenges cannot be stockpiled
+        let second = coin_space.issue_challenge(&farmer, &chunk.name());
+        assert!(first.nonce() != second.nonce());
+        assert!(!coin_space.is_outstanding(&first));
+        match coin_space.mint(&FarmingProof::new(&first, &chunk), &chunk) {
+            Err(::Error::UnknownFarmingChallenge) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // A failed attempt uses up its challenge too
+        coin_space.set_farming_rate(0);
+        let proof = FarmingProof::new(&second, &chunk);
+        match coin_space.mint(&proof, &chunk) {
+            Err(::Error::FarmingAttemptFailed) => (),
+            _ => panic!("Unexpected!"),
+        }
+        coin_space.set_farming_rate(u64::MAX);
+        match coin_space.mint(&proof, &chunk) {
+            Err(::Error::UnknownFarmingChallenge) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn farming_rate() {
+        let farmer = IdType::generate_random().public_keys().0;
+        let chunk = ImmutableData::new(vec![1u8; 1024]);
+        // With a rate of a quarter, roughly a quarter of attempts succeed
+        let mut coin_space = CoinSpace::new(u64::MAX / 4);
+        let successes = (0..400).filter(|_| {
+            let challenge = coin_space.issue_challenge(&farmer, &chunk.name());
+            coin_space.mint(&FarmingProof::new(&challenge, &chunk), &chunk).is_ok()
+        }).count();
+        assert!(successes > 50 && successes < 150);
+    }
+}
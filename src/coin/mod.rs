@@ -15,9 +15,17 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software. 
 
+mod amount;
 mod safecoin;
+mod ledger;
+mod wallet;
+mod minting;
 
+pub use self::amount::*;
 pub use self::safecoin::*;
+pub use self::ledger::*;
+pub use self::wallet::*;
+pub use self::minting::*;
 
 #[test]
 fn dummy() {}
@@ -17,13 +17,18 @@
 
 use cbor;
 use cbor::CborTagEncode;
+use json;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use helper::*;
+use id::IdType;
+use signable::{Signable, SignableBytes};
 use routing::NameType;
 use routing::sendable::Sendable;
-use routing::types::Signature;
+use sodiumoxide::crypto;
 use std::fmt;
-// use sodiumoxide::crypto::sign::{SecretKey, sign_detached};
+use super::amount::*;
 use TypeTag;
 
 /// TypeTag for SafeCoin
@@ -32,30 +37,303 @@ pub struct SafeCoinTypeTag;
 
 impl TypeTag for SafeCoinTypeTag {
     fn type_tag(&self) -> u64 {
-        return 256;
+        ::data_tags::SAFECOIN_TAG
+    }
+}
+
+fn push_u64(combined: &mut Vec<u8>, value: u64) {
+    for i in 0..8 {
+        combined.push((value >> (56 - 8 * i)) as u8);
+    }
+}
+
+fn key_vecs(keys: &[crypto::sign::PublicKey]) -> Vec<Vec<u8>> {
+    keys.iter().map(|key| key.0.to_vec()).collect()
+}
+
+/// Split
+///
+/// Records the division of one state of a coin into several new coins, called outputs.  Every
+/// output carries the whole Split, so each can be checked on its own: the output values must add
+/// up to the value of the coin split.
+#[derive(Clone)]
+pub struct Split {
+    parent: NameType,
+    parent_version: u64,
+    parent_value: Amount,
+    outputs: Vec<(Vec<crypto::sign::PublicKey>, Amount)>,
+}
+
+impl Split {
+    /// Returns the name of the coin which was split
+    pub fn parent(&self) -> &NameType {
+        &self.parent
+    }
+    /// Returns the version of the coin which was split
+    pub fn parent_version(&self) -> u64 {
+        self.parent_version
+    }
+    /// Returns the value of the coin which was split
+    pub fn parent_value(&self) -> Amount {
+        self.parent_value
+    }
+    /// Returns the owners and value of each output
+    pub fn outputs(&self) -> &Vec<(Vec<crypto::sign::PublicKey>, Amount)> {
+        &self.outputs
+    }
+
+    /// Returns the name of the output at index
+    pub fn output_name(&self, index: usize) -> NameType {
+        let mut combined = self.parent.0.to_vec();
+        push_u64(&mut combined, self.parent_version);
+        push_u64(&mut combined, index as u64);
+        NameType(crypto::hash::sha512::hash(&combined).0)
+    }
+
+    /// Checks that no output is empty and that the outputs add up to the parent's value
+    pub fn check_conservation(&self) -> Result<(), ::Error> {
+        if self.outputs.iter().any(|&(_, value)| value.is_zero()) ||
+           try!(Amount::checked_sum(self.outputs.iter().map(|&(_, value)| value))) != self.parent_value {
+            return Err(::Error::ValueNotConserved);
+        }
+        Ok(())
+    }
+}
+
+/// Signs "SafeCoin split", parent, parent version, parent value and the list of outputs, each
+/// written as its value followed by its list of owners.  The owners of the parent sign the whole
+/// split.
+impl Signable for Split {
+    fn signable_bytes(&self) -> Vec<u8> {
+        let written = SignableBytes::new("SafeCoin split").bytes(&self.parent.0).u64(self.parent_version)
+            .u64(self.parent_value.as_nano()).u64(self.outputs.len() as u64);
+        self.outputs.iter().fold(written, |written, &(ref owners, value)| {
+            written.u64(value.as_nano()).public_keys(owners)
+        }).into_bytes()
+    }
+}
+
+impl PartialEq for Split {
+    fn eq(&self, other: &Split) -> bool {
+        self.parent == other.parent &&
+        self.parent_version == other.parent_version &&
+        self.parent_value == other.parent_value &&
+        self.outputs.len() == other.outputs.len() &&
+        self.outputs.iter().zip(other.outputs.iter()).all(|(&(ref lhs_owners, lhs_value), &(ref rhs_owners, rhs_value))| {
+            keys_equal(lhs_owners, rhs_owners) && lhs_value == rhs_value
+        })
+    }
+}
+
+impl fmt::Debug for Split {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Split {{ parent:{:?}, parent_version:{}, parent_value:{}, outputs:{:?} }}",
+            self.parent, self.parent_version, self.parent_value,
+            self.outputs.iter().map(|&(ref owners, value)| (key_vecs(owners), value.as_nano())).collect::<Vec<_>>())
     }
 }
 
 /// SafeCoin
+///
+/// Each transfer moves the owners into previous_owners and must be signed by every one of them.
+/// The version counts the transfers, so a signature cannot be replayed should the coin return to
+/// an earlier set of owners.  A coin may also be split into several coins of smaller value, for
+/// instance to pay part of its value and return the change to its owners.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags, SafeCoin};
+/// use maidsafe_types::coin::Amount;
+///
+/// let payer = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
+/// let payee = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
+/// let coin = SafeCoin::new(payer.name(), vec![payer.public_keys().0]);
+/// let transferred = coin.transfer(vec![payee.public_keys().0], &[&payer]).unwrap();
+/// assert!(transferred.validate().is_ok());
+///
+/// let (payment, change) = transferred.split_payment(vec![payer.public_keys().0], Amount::from_nano(100),
+///                                                   &[&payee]).unwrap();
+/// assert!(payment.validate().is_ok() && change.validate().is_ok());
+/// assert_eq!(change.value(), Amount::coin().checked_sub(Amount::from_nano(100)).unwrap());
+/// ```
 #[derive(Clone)]
 pub struct SafeCoin {
     type_tag: SafeCoinTypeTag,
     name: NameType,
-    owners: Vec<NameType>,
-    previous_owners: Vec<NameType>,
-    signatures: Vec<Signature>
+    version: u64,
+    value: Amount,
+    owners: Vec<crypto::sign::PublicKey>,
+    previous_owners: Vec<crypto::sign::PublicKey>,
+    signatures: Vec<(crypto::sign::PublicKey, crypto::sign::Signature)>,
+    split: Option<Split>,
 }
 
 impl SafeCoin {
-    /// Construct using new()
-    pub fn new(name: NameType, owners: Vec<NameType>, signatures: Vec<Signature>) -> SafeCoin {
+    /// Constructs a newly minted coin worth one whole coin, which has no previous owners
+    pub fn new(name: NameType, owners: Vec<crypto::sign::PublicKey>) -> SafeCoin {
         SafeCoin { type_tag: SafeCoinTypeTag,
                    name: name,
-                   owners: owners.clone(),
-                   previous_owners: owners,
-                   signatures: signatures
+                   version: 0,
+                   value: Amount::coin(),
+                   owners: owners,
+                   previous_owners: Vec::new(),
+                   signatures: Vec::new(),
+                   split: None,
                  }
     }
+
+    /// Returns the number of transfers made since the coin was minted or split off
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+    /// Returns the value of the coin
+    pub fn value(&self) -> Amount {
+        self.value
+    }
+    /// Returns the current owners
+    pub fn owners(&self) -> &Vec<crypto::sign::PublicKey> {
+        &self.owners
+    }
+    /// Returns the owners before the last transfer
+    pub fn previous_owners(&self) -> &Vec<crypto::sign::PublicKey> {
+        &self.previous_owners
+    }
+    /// Returns the previous owners' signatures over the last transfer
+    pub fn signatures(&self) -> &Vec<(crypto::sign::PublicKey, crypto::sign::Signature)> {
+        &self.signatures
+    }
+    /// Returns the Split the coin was created by, if it was not minted
+    pub fn split(&self) -> Option<&Split> {
+        self.split.as_ref()
+    }
+
+    // Signs message with the IdType of each current owner
+    fn sign_as_owners(&self, message: &[u8], signing_ids: &[&IdType])
+            -> Result<Vec<(crypto::sign::PublicKey, crypto::sign::Signature)>, ::Error> {
+        let mut signatures = Vec::with_capacity(self.owners.len());
+        for owner in &self.owners {
+            match signing_ids.iter().find(|id| slice_equal(&id.public_keys().0 .0, &owner.0)) {
+                Some(id) => signatures.push((owner.clone(), id.sign_detached(message))),
+                None => return Err(::Error::InsufficientSignatures {
+                                required: self.owners.len(),
+                                actual: signatures.len() }),
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Returns the coin transferred to new_owners.  signing_ids must include the IdType of every
+    /// current owner; ids which are not owners are ignored.
+    pub fn transfer(&self, new_owners: Vec<crypto::sign::PublicKey>, signing_ids: &[&IdType])
+            -> Result<SafeCoin, ::Error> {
+        let version = try!(self.version.checked_add(1).ok_or(::Error::InvalidVersion {
+            expected: self.version,
+            actual: self.version,
+        }));
+        let mut transferred = SafeCoin { type_tag: SafeCoinTypeTag,
+                                         name: self.name.clone(),
+                                         version: version,
+                                         value: self.value,
+                                         owners: new_owners,
+                                         previous_owners: self.owners.clone(),
+                                         signatures: Vec::new(),
+                                         split: self.split.clone(),
+                                       };
+        transferred.signatures = try!(self.sign_as_owners(&transferred.signable_bytes(), signing_ids));
+        Ok(transferred)
+    }
+
+    /// Splits the coin into one output for each of the given owners and values, which must add up
+    /// to the coin's value.  signing_ids must include the IdType of every current owner.
+    pub fn split_into(&self, outputs: Vec<(Vec<crypto::sign::PublicKey>, Amount)>, signing_ids: &[&IdType])
+            -> Result<Vec<SafeCoin>, ::Error> {
+        let split = Split { parent: self.name.clone(),
+                            parent_version: self.version,
+                            parent_value: self.value,
+                            outputs: outputs,
+                          };
+        try!(split.check_conservation());
+        let signatures = try!(self.sign_as_owners(&split.signable_bytes(), signing_ids));
+        Ok(split.outputs.iter().enumerate().map(|(index, &(ref owners, value))| {
+            SafeCoin { type_tag: SafeCoinTypeTag,
+                       name: split.output_name(index),
+                       version: 0,
+                       value: value,
+                       owners: owners.clone(),
+                       previous_owners: self.owners.clone(),
+                       signatures: signatures.clone(),
+                       split: Some(split.clone()),
+                     }
+        }).collect())
+    }
+
+    /// Pays part of the coin's value to payee_owners, returning the payment and the change, which
+    /// stays with the current owners.  To pay the whole value use `transfer`.
+    pub fn split_payment(&self, payee_owners: Vec<crypto::sign::PublicKey>, payment: Amount,
+                         signing_ids: &[&IdType]) -> Result<(SafeCoin, SafeCoin), ::Error> {
+        let change = try!(self.value.checked_sub(payment).map_err(|_| {
+            ::Error::InsufficientFunds { required: payment, available: self.value }
+        }));
+        let mut outputs = try!(self.split_into(vec![(payee_owners, payment), (self.owners.clone(), change)],
+                                               signing_ids));
+        let change = outputs.pop().unwrap();
+        Ok((outputs.pop().unwrap(), change))
+    }
+
+    // Only a newly minted coin may have no previous owners; anything else must be signed by someone
+    fn check_signatures(&self, message: &[u8]) -> Result<(), ::Error> {
+        if self.previous_owners.is_empty() {
+            return Err(::Error::InsufficientSignatures { required: 1, actual: 0 });
+        }
+        let valid = self.previous_owners.iter().filter(|owner| {
+            self.signatures.iter().any(|&(ref key, ref signature)| {
+                slice_equal(&key.0, &owner.0) && crypto::sign::verify_detached(signature, message, owner)
+            })
+        }).count();
+        if valid < self.previous_owners.len() {
+            return Err(::Error::InsufficientSignatures { required: self.previous_owners.len(), actual: valid });
+        }
+        Ok(())
+    }
+
+    /// Checks that every previous owner has signed the transfer to the current owners or, for a
+    /// coin just split off, the split.  The outputs of a split must conserve its value.  Only a
+    /// coin which has just been minted, at version 0 and not split off, may have no previous
+    /// owners; it has nothing to check, so vaults must still compare previous_owners against the
+    /// owners of the copy they hold.
+    pub fn validate(&self) -> Result<(), ::Error> {
+        if self.version != 0 {
+            return self.check_signatures(&self.signable_bytes());
+        }
+        match self.split {
+            Some(ref split) => {
+                try!(split.check_conservation());
+                let index = match (0..split.outputs.len()).find(|index| split.output_name(*index) == self.name) {
+                    Some(index) => index,
+                    None => return Err(::Error::NameMismatch),
+                };
+                let (ref owners, value) = split.outputs[index];
+                if value != self.value || !keys_equal(owners, &self.owners) {
+                    return Err(::Error::ValueNotConserved);
+                }
+                self.check_signatures(&split.signable_bytes())
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+/// A coin just split off signs its Split.  Otherwise the previous owners sign "SafeCoin transfer",
+/// name, version, value and the list of new owners.
+impl Signable for SafeCoin {
+    fn signable_bytes(&self) -> Vec<u8> {
+        match (self.version, &self.split) {
+            (0, &Some(ref split)) => split.signable_bytes(),
+            _ => SignableBytes::new("SafeCoin transfer").bytes(&self.name.0).u64(self.version)
+                     .u64(self.value.as_nano()).public_keys(&self.owners).into_bytes(),
+        }
+    }
 }
 
 impl Sendable for SafeCoin {
@@ -84,65 +362,167 @@ impl PartialEq for SafeCoin {
     fn eq(&self, other: &SafeCoin) -> bool {
         &self.type_tag.type_tag() == &other.type_tag.type_tag() &&
         self.name == other.name &&
-        self.owners == other.owners &&
-        self.previous_owners == other.previous_owners &&
-        self.signatures == other.signatures
+        self.version == other.version &&
+        self.value == other.value &&
+        keys_equal(&self.owners, &other.owners) &&
+        keys_equal(&self.previous_owners, &other.previous_owners) &&
+        self.signatures.len() == other.signatures.len() &&
+        self.signatures.iter().zip(other.signatures.iter()).all(|(&(ref lhs_key, ref lhs_signature), &(ref rhs_key, ref rhs_signature))| {
+            slice_equal(&lhs_key.0, &rhs_key.0) && slice_equal(&lhs_signature.0, &rhs_signature.0)
+        }) &&
+        self.split == other.split
     }
 }
 
 impl fmt::Debug for SafeCoin {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "SafeCoin {{ type_tag:{:?}, name:{:?}, owners:{:?}, previous_owners:{:?}, signatures:{:?}}}",
-            self.type_tag.type_tag(), self.name, self.owners, self.previous_owners, self.signatures)
+        write!(f, "SafeCoin {{ type_tag:{:?}, name:{:?}, version:{}, value:{}, owners:{:?}, previous_owners:{:?}, signatures:{:?}, split:{:?}}}",
+            self.type_tag.type_tag(), self.name, self.version, self.value,
+            key_vecs(&self.owners), key_vecs(&self.previous_owners),
+            self.signatures.iter().map(|&(ref key, ref signature)| (key.0.to_vec(), signature.0.to_vec())).collect::<Vec<_>>(),
+            self.split)
     }
 }
 
 impl Encodable for SafeCoin {
     fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
-        CborTagEncode::new(5483_003, &(&self.name, &self.owners, &self.previous_owners, &self.signatures)).encode(e)
+        let signer_key_vecs = self.signatures.iter().map(|&(ref key, _)| key.0.to_vec()).collect::<Vec<_>>();
+        let signature_vecs = self.signatures.iter().map(|&(_, ref signature)| signature.0.to_vec()).collect::<Vec<_>>();
+        // Holds the split, if any, as a list of zero or one items
+        let splits = self.split.iter().map(|split| {
+            (&split.parent, split.parent_version, split.parent_value.as_nano(),
+             split.outputs.iter().map(|&(ref owners, value)| (key_vecs(owners), value.as_nano())).collect::<Vec<_>>())
+        }).collect::<Vec<_>>();
+        CborTagEncode::new(::data_tags::SAFECOIN_TAG,
+                           &(&self.name, self.version, self.value.as_nano(), key_vecs(&self.owners),
+                             key_vecs(&self.previous_owners), signer_key_vecs, signature_vecs, splits)).encode(e)
     }
 }
 
 impl Decodable for SafeCoin {
     fn decode<D: Decoder>(d: &mut D) -> Result<SafeCoin, D::Error> {
-        try!(d.read_u64());
-        let (name, owners, previous_owners, signatures) = try!(Decodable::decode(d));
+        let (name, version, value, owner_key_vecs, previous_owner_key_vecs, signer_key_vecs, signature_vecs, splits):
+            (NameType, u64, u64, Vec<Vec<u8>>, Vec<Vec<u8>>, Vec<Vec<u8>>, Vec<Vec<u8>>,
+             Vec<(NameType, u64, u64, Vec<(Vec<Vec<u8>>, u64)>)>) = try!(Decodable::decode(d));
+        let signer_keys = try_decode!(d, vecs_to_public_keys(signer_key_vecs));
+        let signatures = try_decode!(d, vecs_to_signatures(signature_vecs));
+        if signer_keys.len() != signatures.len() {
+            return Err(d.error("Mismatched SafeCoin signer keys and signatures"));
+        }
+        if splits.len() > 1 {
+            return Err(d.error("SafeCoin holds more than one split"));
+        }
+        let mut split = None;
+        for (parent, parent_version, parent_value, output_vecs) in splits {
+            let mut outputs = Vec::with_capacity(output_vecs.len());
+            for (owner_vecs, value) in output_vecs {
+                outputs.push((try_decode!(d, vecs_to_public_keys(owner_vecs)), Amount::from_nano(value)));
+            }
+            split = Some(Split { parent: parent,
+                                 parent_version: parent_version,
+                                 parent_value: Amount::from_nano(parent_value),
+                                 outputs: outputs,
+                               });
+        }
         let safecoin = SafeCoin { type_tag: SafeCoinTypeTag,
                                   name: name,
-                                  owners: owners,
-                                  previous_owners: previous_owners,
-                                  signatures: signatures
+                                  version: version,
+                                  value: Amount::from_nano(value),
+                                  owners: try_decode!(d, vecs_to_public_keys(owner_key_vecs)),
+                                  previous_owners: try_decode!(d, vecs_to_public_keys(previous_owner_key_vecs)),
+                                  signatures: signer_keys.into_iter().zip(signatures.into_iter()).collect(),
+                                  split: split,
                                 };
         Ok(safecoin)
     }
 }
 
+impl ToJson for Split {
+    fn to_json(&self) -> Json {
+        let outputs = self.outputs.iter().map(|&(ref owners, value)| {
+            ObjectBuilder::untagged().field("owners", json::public_keys(owners))
+                                     .field("value", value.as_nano())
+                                     .build()
+        }).collect::<Vec<_>>();
+        ObjectBuilder::untagged().bytes("parent", &self.parent.0)
+                                 .field("parent_version", self.parent_version)
+                                 .field("parent_value", self.parent_value.as_nano())
+                                 .field("outputs", outputs)
+                                 .build()
+    }
+}
+
+impl FromJson for Split {
+    fn from_json(json: &Json) -> Result<Split, ::Error> {
+        let object = try!(Object::new(json));
+        let mut outputs = Vec::new();
+        for output in try!(object.array("outputs")) {
+            let output = try!(Object::new(output));
+            outputs.push((try!(output.public_keys("owners")), Amount::from_nano(try!(output.u64("value")))));
+        }
+        Ok(Split { parent: try!(object.name_type("parent")),
+                   parent_version: try!(object.u64("parent_version")),
+                   parent_value: Amount::from_nano(try!(object.u64("parent_value"))),
+                   outputs: outputs,
+                 })
+    }
+}
+
+impl ToJson for SafeCoin {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::SAFECOIN_TAG)
+            .bytes("name", &self.name.0)
+            .field("version", self.version)
+            .field("value", self.value.as_nano())
+            .field("owners", json::public_keys(&self.owners))
+            .field("previous_owners", json::public_keys(&self.previous_owners))
+            .field("signatures", json::signatures(&self.signatures))
+            .field("split", self.split.to_json())
+            .build()
+    }
+}
+
+impl FromJson for SafeCoin {
+    fn from_json(json: &Json) -> Result<SafeCoin, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::SAFECOIN_TAG]));
+        let split = match *try!(object.field("split")) {
+            Json::Null => None,
+            ref split => Some(try!(Split::from_json(split))),
+        };
+        Ok(SafeCoin { type_tag: SafeCoinTypeTag,
+                      name: try!(object.name_type("name")),
+                      version: try!(object.u64("version")),
+                      value: Amount::from_nano(try!(object.u64("value"))),
+                      owners: try!(object.public_keys("owners")),
+                      previous_owners: try!(object.public_keys("previous_owners")),
+                      signatures: try!(object.signatures("signatures")),
+                      split: split,
+                    })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use cbor;
+    use coin::Amount;
+    use json;
+    use id::{IdType, RevocationIdType};
     use routing::NameType;
     use routing::types::{vector_as_u8_64_array, generate_random_vec_u8};
-    use routing::types::Signature;
     use routing::sendable::Sendable;
+    use rustc_serialize::hex::ToHex;
+    use signable::Signable;
+    use signable::expected::{field, number};
+    use sodiumoxide::crypto;
     use Random;
 
     impl Random for SafeCoin {
         fn generate_random() -> SafeCoin {
             let name = NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64)));
-            let mut owners = Vec::<NameType>::new();
-            owners.push(NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64))));
-            let mut previous_owners = Vec::<NameType>::new();
-            previous_owners.push(NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64))));
-            let mut signatures = Vec::<Signature>::new();
-            signatures.push(Signature { signature:  generate_random_vec_u8(64)});
-
-            SafeCoin {
-                type_tag: SafeCoinTypeTag,
-                name: name,
-                owners: owners,
-                previous_owners: previous_owners,
-                signatures: signatures
-            }
+            let owner = IdType::generate_random();
+            SafeCoin::new(name, vec![owner.public_keys().0])
+                .transfer(vec![IdType::generate_random().public_keys().0], &[&owner]).unwrap()
         }
     }
 
@@ -150,6 +530,259 @@ mod test {
     fn create_safecoin() {
         let safecoin = SafeCoin::generate_random();
         assert_eq!(safecoin, safecoin);
-        assert_eq!(safecoin.type_tag(), 256u64);
+        assert_eq!(safecoin.type_tag(), ::data_tags::SAFECOIN_TAG);
+        assert_eq!(safecoin.value(), Amount::coin());
+    }
+
+    #[test]
+    fn transfer_and_validate() {
+        let name = NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64)));
+        let owners = vec![IdType::generate_random(), IdType::generate_random()];
+        let owner_keys = owners.iter().map(|id| id.public_keys().0).collect::<Vec<_>>();
+        let new_owner = IdType::generate_random();
+        let stranger = RevocationIdType::generate_random();
+        let stranger_id = IdType::new(&stranger);
+
+        let minted = SafeCoin::new(name, owner_keys.clone());
+        assert_eq!(minted.version(), 0);
+        assert!(minted.validate().is_ok());
+
+        // Every owner must sign
+        match minted.transfer(vec![new_owner.public_keys().0], &[&owners[0], &stranger_id]) {
+            Err(::Error::InsufficientSignatures { required: 2, .. }) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let transferred = minted.transfer(vec![new_owner.public_keys().0],
+                                          &[&stranger_id, &owners[1], &owners[0]]).unwrap();
+        assert_eq!(transferred.version(), 1);
+        assert_eq!(transferred.previous_owners().iter().map(|key| key.0.to_vec()).collect::<Vec<_>>(),
+                   owner_keys.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>());
+        assert!(transferred.validate().is_ok());
+
+        // Redirecting the coin invalidates the signatures
+        let mut redirected = transferred.clone();
+        redirected.owners = vec![stranger_id.public_keys().0];
+        assert!(redirected.validate().is_err());
+
+        // As does raising its value
+        let mut inflated = transferred.clone();
+        inflated.value = Amount::from_coins(2).unwrap();
+        assert!(inflated.validate().is_err());
+
+        // Or dropping one of them
+        let mut unsigned = transferred.clone();
+        let _ = unsigned.signatures.pop();
+        match unsigned.validate() {
+            Err(::Error::InsufficientSignatures { required: 2, actual: 1 }) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Signatures from an earlier transfer cannot be replayed once the owners repeat
+        let returned = transferred.transfer(owner_keys.clone(), &[&new_owner]).unwrap();
+        let again = returned.transfer(vec![new_owner.public_keys().0], &[&owners[0], &owners[1]]).unwrap();
+        let mut replayed = again.clone();
+        replayed.signatures = transferred.signatures.clone();
+        assert!(again.validate().is_ok());
+        assert!(replayed.validate().is_err());
+
+        // A transferred coin cannot shed its previous owners to skip the signature check
+        let mut orphaned = transferred.clone();
+        orphaned.previous_owners = Vec::new();
+        orphaned.signatures = Vec::new();
+        match orphaned.validate() {
+            Err(::Error::InsufficientSignatures { required: 1, actual: 0 }) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // The version cannot wrap around to look newly minted
+        let mut exhausted = transferred.clone();
+        exhausted.version = ::std::u64::MAX;
+        match exhausted.transfer(owner_keys.clone(), &[&new_owner]) {
+            Err(::Error::InvalidVersion { .. }) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn split_payment() {
+        let payer = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let coin = SafeCoin::new(NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64))),
+                                 vec![payer.public_keys().0]);
+        let price = Amount::from_nano(300_000_000);
+
+        let (payment, change) = coin.split_payment(vec![payee.public_keys().0], price, &[&payer]).unwrap();
+        assert_eq!(payment.value(), price);
+        assert_eq!(change.value(), Amount::from_nano(700_000_000));
+        assert_eq!(payment.owners()[0].0, payee.public_keys().0 .0);
+        assert_eq!(change.owners()[0].0, payer.public_keys().0 .0);
+        assert!(payment.name() != change.name() && payment.name() != coin.name());
+        assert_eq!(payment.split().unwrap().parent(), &coin.name());
+        assert!(payment.validate().is_ok());
+        assert!(change.validate().is_ok());
+
+        // Outputs can be transferred and split again in turn
+        let passed_on = payment.transfer(vec![payer.public_keys().0], &[&payee]).unwrap();
+        assert!(passed_on.validate().is_ok());
+        let (_, small_change) = passed_on.split_payment(vec![payee.public_keys().0], Amount::from_nano(1),
+                                                        &[&payer]).unwrap();
+        assert!(small_change.validate().is_ok());
+
+        match coin.split_payment(vec![payee.public_keys().0], Amount::from_coins(2).unwrap(), &[&payer]) {
+            Err(::Error::InsufficientFunds { .. }) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match coin.split_payment(vec![payee.public_keys().0], Amount::coin(), &[&payer]) {
+            Err(::Error::ValueNotConserved) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(coin.split_payment(vec![payee.public_keys().0], price, &[&payee]).is_err());
+    }
+
+    #[test]
+    fn split_conserves_value() {
+        let payer = IdType::generate_random();
+        let coin = SafeCoin::new(NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64))),
+                                 vec![payer.public_keys().0]);
+        let third = Amount::from_nano(Amount::coin().as_nano() / 3);
+        let outputs = vec![(vec![payer.public_keys().0], third),
+                           (vec![payer.public_keys().0], third)];
+        match coin.split_into(outputs.clone(), &[&payer]) {
+            Err(::Error::ValueNotConserved) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let mut outputs = outputs;
+        outputs.push((vec![payer.public_keys().0], Amount::coin().checked_sub(third).unwrap()
+                                                                 .checked_sub(third).unwrap()));
+        let split = coin.split_into(outputs, &[&payer]).unwrap();
+        assert_eq!(split.len(), 3);
+        for output in &split {
+            assert!(output.validate().is_ok());
+        }
+
+        // An output which claims more than the split gave it
+        let mut inflated = split[0].clone();
+        inflated.value = Amount::coin();
+        match inflated.validate() {
+            Err(::Error::ValueNotConserved) => (),
+            _ => panic!("Unexpected!"),
+        }
+        // Or a split which creates value
+        let mut forged = split[0].clone();
+        if let Some(ref mut split) = forged.split {
+            split.outputs[1].1 = Amount::coin();
+        }
+        assert!(forged.validate().is_err());
+        // Or an output which drops the parent's owners so that nobody need have signed the split
+        let mut unsigned = split[0].clone();
+        unsigned.previous_owners = Vec::new();
+        unsigned.signatures = Vec::new();
+        match unsigned.validate() {
+            Err(::Error::InsufficientSignatures { required: 1, actual: 0 }) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn signable_bytes() {
+        let payer = IdType::generate_random();
+        let payee = IdType::generate_random();
+        let coin = SafeCoin::new(NameType([7u8; 64]), vec![payer.public_keys().0]);
+        let transferred = coin.transfer(vec![payee.public_keys().0], &[&payer]).unwrap();
+        let mut expected = field(b"SafeCoin transfer");
+        expected.extend(field(&[7u8; 64]));
+        expected.extend(number(1));
+        expected.extend(number(1_000_000_000));
+        expected.extend(number(1));
+        expected.extend(field(&payee.public_keys().0 .0));
+        assert_eq!(transferred.signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(&transferred.signatures()[0].1, &expected, &payer.public_keys().0));
+
+        let (payment, change) = transferred.split_payment(vec![payer.public_keys().0], Amount::from_nano(100),
+                                                          &[&payee]).unwrap();
+        let mut expected = field(b"SafeCoin split");
+        expected.extend(field(&[7u8; 64]));
+        expected.extend(number(1));
+        expected.extend(number(1_000_000_000));
+        expected.extend(number(2));
+        expected.extend(number(100));
+        expected.extend(number(1));
+        expected.extend(field(&payer.public_keys().0 .0));
+        expected.extend(number(999_999_900));
+        expected.extend(number(1));
+        expected.extend(field(&payee.public_keys().0 .0));
+        assert_eq!(payment.split().unwrap().signable_bytes(), expected);
+        assert_eq!(payment.signable_bytes(), expected);
+        assert_eq!(change.signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(&payment.signatures()[0].1, &expected, &payee.public_keys().0));
+
+        let fixed = SafeCoin { type_tag: SafeCoinTypeTag,
+                               name: NameType([3u8; 64]),
+                               version: 1,
+                               value: Amount::coin(),
+                               owners: vec![crypto::sign::PublicKey([1u8; 32])],
+                               previous_owners: Vec::new(),
+                               signatures: Vec::new(),
+                               split: None,
+                             };
+        assert_eq!(fixed.signable_bytes().to_hex(),
+                   concat!("000000000000001153616665436f696e207472616e73666572000000000000004003030303030303",
+                           "03030303030303030303030303030303030303030303030303030303030303030303030303030303",
+                           "03030303030303030303030303030303030000000000000001000000003b9aca0000000000000000",
+                           "01000000000000002001010101010101010101010101010101010101010101010101010101010101",
+                           "01"));
+        let fixed_split = Split { parent: NameType([3u8; 64]),
+                                  parent_version: 1,
+                                  parent_value: Amount::coin(),
+                                  outputs: vec![(vec![crypto::sign::PublicKey([2u8; 32])], Amount::from_nano(100)),
+                                                (vec![crypto::sign::PublicKey([1u8; 32])], Amount::from_nano(999_999_900))],
+                                };
+        assert_eq!(fixed_split.signable_bytes().to_hex(),
+                   concat!("000000000000000e53616665436f696e2073706c6974000000000000004003030303030303030303",
+                           "03030303030303030303030303030303030303030303030303030303030303030303030303030303",
+                           "03030303030303030303030303030000000000000001000000003b9aca0000000000000000020000",
+                           "00000000006400000000000000010000000000000020020202020202020202020202020202020202",
+                           "0202020202020202020202020202000000003b9ac99c000000000000000100000000000000200101",
+                           "010101010101010101010101010101010101010101010101010101010101"));
+    }
+
+    #[test]
+    fn serialisation_safecoin() {
+        let obj_before = SafeCoin::generate_random();
+        let owner = IdType::generate_random();
+        let (payment, _) = SafeCoin::new(obj_before.name(), vec![owner.public_keys().0])
+                               .split_payment(vec![owner.public_keys().0], Amount::from_nano(5), &[&owner]).unwrap();
+
+        for coin in vec![obj_before, payment] {
+            let mut e = cbor::Encoder::from_memory();
+            e.encode(&[&coin]).unwrap();
+
+            match ::AnyData::from_bytes(e.as_bytes()) {
+                Ok(::AnyData::SafeCoin(obj_after)) => {
+                    assert!(obj_after.validate().is_ok());
+                    assert_eq!(coin, obj_after);
+                },
+                _ => panic!("Unexpected!"),
+            }
+        }
+    }
+
+    #[test]
+    fn json_safecoin() {
+        let owner = IdType::generate_random();
+        let coin = SafeCoin::new(NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64))),
+                                 vec![owner.public_keys().0]);
+        let (payment, _) = coin.split_payment(vec![owner.public_keys().0], Amount::from_nano(5), &[&owner]).unwrap();
+
+        for coin in vec![SafeCoin::generate_random(), payment] {
+            let obj_after = json::from_str::<SafeCoin>(&json::to_string(&coin)).unwrap();
+            assert!(obj_after.validate().is_ok());
+            assert_eq!(coin, obj_after);
+            assert_eq!(coin.serialised_contents(), obj_after.serialised_contents());
+        }
+        let unsplit = json::parse(&json::to_string(&coin)).unwrap();
+        assert_eq!(unsplit.find("split"), Some(&::rustc_serialize::json::Json::Null));
     }
 }
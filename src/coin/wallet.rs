@@ -0,0 +1,341 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor::CborTagEncode;
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use helper::*;
+use id::{IdType, PublicIdType};
+use json::{FromJson, Object, ObjectBuilder};
+use routing::NameType;
+use routing::sendable::Sendable;
+use rustc_serialize::json::{Json, ToJson};
+use sodiumoxide::crypto;
+use std::fmt;
+use super::amount::*;
+use super::safecoin::*;
+
+/// Wallet
+///
+/// The SafeCoins owned solely by the IdType of a PublicIdType.  Payments which do not use up a coin
+/// split it, and the change is kept in the wallet.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MpidTypeTags, PublicIdType, SafeCoin};
+/// use maidsafe_types::coin::{Amount, Wallet};
+///
+/// let revocation_mpid = RevocationIdType::new::<MpidTypeTags>();
+/// let mpid = IdType::new(&revocation_mpid);
+/// let mut wallet = Wallet::new(&PublicIdType::new(&mpid, &revocation_mpid));
+/// wallet.add(SafeCoin::new(mpid.name(), vec![mpid.public_keys().0])).unwrap();
+/// assert_eq!(wallet.balance().unwrap(), Amount::coin());
+///
+/// let payee = IdType::new(&revocation_mpid);
+/// let transfers = wallet.pay(&mpid, vec![payee.public_keys().0], Amount::from_nano(250_000_000)).unwrap();
+/// assert_eq!(transfers.len(), 2);
+/// assert_eq!(wallet.balance().unwrap(), Amount::from_nano(750_000_000));
+/// ```
+#[derive(Clone)]
+pub struct Wallet {
+    owner_name: NameType,
+    owner: crypto::sign::PublicKey,
+    coins: Vec<SafeCoin>,
+}
+
+impl Wallet {
+    /// Constructs an empty wallet for the IdType of public_id
+    pub fn new(public_id: &PublicIdType) -> Wallet {
+        Wallet { owner_name: public_id.name(), owner: public_id.public_keys().0, coins: Vec::new() }
+    }
+
+    /// Returns the name of the owning PublicIdType
+    pub fn owner_name(&self) -> &NameType {
+        &self.owner_name
+    }
+    /// Returns the signing key which must own every coin in the wallet
+    pub fn owner(&self) -> &crypto::sign::PublicKey {
+        &self.owner
+    }
+    /// Returns the coins held
+    pub fn coins(&self) -> &[SafeCoin] {
+        &self.coins
+    }
+    /// Returns the total value of the coins held
+    pub fn balance(&self) -> Result<Amount, ::Error> {
+        Amount::checked_sum(self.coins.iter().map(|coin| coin.value()))
+    }
+
+    /// Adds a coin which has been transferred to the wallet's owner, replacing any earlier state
+    /// of the same coin
+    pub fn add(&mut self, coin: SafeCoin) -> Result<(), ::Error> {
+        if coin.owners().len() != 1 || !slice_equal(&coin.owners()[0].0, &self.owner.0) {
+            return Err(::Error::NotOwner);
+        }
+        let name = coin.name();
+        self.coins.retain(|held| held.name() != name);
+        self.coins.push(coin);
+        Ok(())
+    }
+
+    /// Chooses coins worth at least amount, without removing them from the wallet.  Only the last
+    /// coin chosen may be worth more than is still needed.
+    pub fn select_coins(&self, amount: Amount) -> Result<Vec<&SafeCoin>, ::Error> {
+        let mut selected = Vec::new();
+        let mut total = Amount::zero();
+        for coin in &self.coins {
+            if total >= amount {
+                break;
+            }
+            total = try!(total.checked_add(coin.value()));
+            selected.push(coin);
+        }
+        if total < amount {
+            return Err(::Error::InsufficientFunds { required: amount, available: total });
+        }
+        Ok(selected)
+    }
+
+    /// Pays amount to new_owners, signing with id.  Whole coins are transferred, and the last coin
+    /// is split if only part of it is needed, in which case the change is added to the wallet.  The
+    /// spent coins are removed.  The returned transfers and split outputs, including the change,
+    /// are to be submitted to the network together.
+    pub fn pay(&mut self, id: &IdType, new_owners: Vec<crypto::sign::PublicKey>, amount: Amount)
+            -> Result<Vec<SafeCoin>, ::Error> {
+        if !slice_equal(&id.public_keys().0 .0, &self.owner.0) {
+            return Err(::Error::NotOwner);
+        }
+        let (spent, transfers, change) = {
+            let selected = try!(self.select_coins(amount));
+            let mut transfers = Vec::with_capacity(selected.len() + 1);
+            let mut change = None;
+            let mut remaining = amount;
+            for coin in &selected {
+                if coin.value() <= remaining {
+                    remaining = try!(remaining.checked_sub(coin.value()));
+                    transfers.push(try!(coin.transfer(new_owners.clone(), &[id])));
+                } else {
+                    let (payment, coin_change) = try!(coin.split_payment(new_owners.clone(), remaining, &[id]));
+                    transfers.push(payment);
+                    transfers.push(coin_change.clone());
+                    change = Some(coin_change);
+                }
+            }
+            (selected.iter().map(|coin| coin.name()).collect::<Vec<_>>(), transfers, change)
+        };
+        self.coins.retain(|held| !spent.contains(&held.name()));
+        if let Some(change) = change {
+            try!(self.add(change));
+        }
+        Ok(transfers)
+    }
+}
+
+impl PartialEq for Wallet {
+    fn eq(&self, other: &Wallet) -> bool {
+        self.owner_name == other.owner_name &&
+        slice_equal(&self.owner.0, &other.owner.0) &&
+        self.coins == other.coins
+    }
+}
+
+impl fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Wallet {{ owner_name:{:?}, owner:{:?}, coins:{:?} }}",
+            self.owner_name, self.owner.0.to_vec(), self.coins)
+    }
+}
+
+impl Encodable for Wallet {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        // Each coin carries its own tag, so is nested in its serialised form
+        let coin_vecs = self.coins.iter().map(|coin| coin.serialised_contents()).collect::<Vec<_>>();
+        CborTagEncode::new(::data_tags::WALLET_TAG,
+                           &(&self.owner_name, self.owner.0.as_ref(), coin_vecs)).encode(e)
+    }
+}
+
+impl Decodable for Wallet {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Wallet, D::Error> {
+        let (owner_name, owner_vec, coin_vecs): (NameType, Vec<u8>, Vec<Vec<u8>>) = try!(Decodable::decode(d));
+        let mut owners = try_decode!(d, vecs_to_public_keys(vec![owner_vec]));
+        let mut wallet = Wallet { owner_name: owner_name, owner: owners.remove(0), coins: Vec::new() };
+        for coin_vec in coin_vecs {
+            match try_decode!(d, ::AnyData::from_bytes(&coin_vec)) {
+                ::AnyData::SafeCoin(coin) => try_decode!(d, wallet.add(coin)),
+                _ => return Err(d.error("Wallet holds an item which is not a SafeCoin")),
+            }
+        }
+        Ok(wallet)
+    }
+}
+
+impl ToJson for Wallet {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::WALLET_TAG)
+            .bytes("owner_name", &self.owner_name.0)
+            .bytes("owner", &self.owner.0)
+            .field("coins", self.coins.to_json())
+            .build()
+    }
+}
+
+impl FromJson for Wallet {
+    fn from_json(json: &Json) -> Result<Wallet, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::WALLET_TAG]));
+        let mut wallet = Wallet { owner_name: try!(object.name_type("owner_name")),
+                                  owner: try!(object.public_key("owner")),
+                                  coins: Vec::new(),
+                                };
+        for coin in try!(object.array("coins")) {
+            try!(wallet.add(try!(SafeCoin::from_json(coin))));
+        }
+        Ok(wallet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cbor;
+    use helper::slice_equal;
+    use coin::{Amount, SafeCoin};
+    use json;
+    use id::{IdType, RevocationIdType, PublicIdType};
+    use routing::NameType;
+    use routing::sendable::Sendable;
+    use routing::types::{vector_as_u8_64_array, generate_random_vec_u8};
+    use rustc_serialize::json::ToJson;
+    use MpidTypeTags;
+    use Random;
+
+    fn owned_wallet(coins: usize) -> (IdType, Wallet) {
+        let revocation_mpid = RevocationIdType::new::<MpidTypeTags>();
+        let mpid = IdType::new(&revocation_mpid);
+        let mut wallet = Wallet::new(&PublicIdType::new(&mpid, &revocation_mpid));
+        for _ in 0..coins {
+            let name = NameType::new(vector_as_u8_64_array(generate_random_vec_u8(64)));
+            wallet.add(SafeCoin::new(name, vec![mpid.public_keys().0])).unwrap();
+        }
+        (mpid, wallet)
+    }
+
+    #[test]
+    fn add_and_select() {
+        let (mpid, mut wallet) = owned_wallet(3);
+        assert_eq!(wallet.balance().unwrap(), Amount::from_coins(3).unwrap());
+
+        // Adding a later state of a held coin replaces it
+        let coin = wallet.coins()[0].clone();
+        let returned = coin.transfer(vec![mpid.public_keys().0], &[&mpid]).unwrap();
+        wallet.add(returned.clone()).unwrap();
+        assert_eq!(wallet.balance().unwrap(), Amount::from_coins(3).unwrap());
+        assert!(wallet.coins().contains(&returned));
+
+        match wallet.add(SafeCoin::generate_random()) {
+            Err(::Error::NotOwner) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        assert_eq!(wallet.select_coins(Amount::from_coins(2).unwrap()).unwrap().len(), 2);
+        assert_eq!(wallet.select_coins(Amount::from_nano(1_500_000_000)).unwrap().len(), 2);
+        assert_eq!(wallet.select_coins(Amount::zero()).unwrap().len(), 0);
+        match wallet.select_coins(Amount::from_coins(4).unwrap()) {
+            Err(::Error::InsufficientFunds { required, available }) => {
+                assert_eq!(required, Amount::from_coins(4).unwrap());
+                assert_eq!(available, Amount::from_coins(3).unwrap());
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn pay() {
+        let (mpid, mut wallet) = owned_wallet(3);
+        let payee = IdType::generate_random();
+
+        assert!(wallet.pay(&payee, vec![payee.public_keys().0], Amount::coin()).is_err());
+        assert!(wallet.pay(&mpid, vec![payee.public_keys().0], Amount::from_coins(4).unwrap()).is_err());
+        assert_eq!(wallet.balance().unwrap(), Amount::from_coins(3).unwrap());
+
+        let transfers = wallet.pay(&mpid, vec![payee.public_keys().0], Amount::from_coins(2).unwrap()).unwrap();
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(wallet.balance().unwrap(), Amount::coin());
+        for transfer in &transfers {
+            assert!(transfer.validate().is_ok());
+            assert!(!wallet.coins().iter().any(|coin| coin.name() == transfer.name()));
+        }
+    }
+
+    #[test]
+    fn pay_with_change() {
+        let (mpid, mut wallet) = owned_wallet(2);
+        let payee = IdType::generate_random();
+        let price = Amount::from_nano(1_250_000_000);
+
+        let transfers = wallet.pay(&mpid, vec![payee.public_keys().0], price).unwrap();
+        // A whole coin, then the payment and change split from the second
+        assert_eq!(transfers.len(), 3);
+        let paid = transfers.iter().filter(|coin| slice_equal(&coin.owners()[0].0, &payee.public_keys().0 .0))
+                                   .map(|coin| coin.value());
+        assert_eq!(Amount::checked_sum(paid).unwrap(), price);
+        for transfer in &transfers {
+            assert!(transfer.validate().is_ok());
+        }
+
+        // Only the change is left
+        assert_eq!(wallet.coins().len(), 1);
+        assert_eq!(wallet.coins()[0], transfers[2]);
+        assert_eq!(wallet.balance().unwrap(), Amount::from_nano(750_000_000));
+
+        // And can be spent in turn
+        let transfers = wallet.pay(&mpid, vec![payee.public_keys().0], Amount::from_nano(750_000_000)).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert!(wallet.balance().unwrap().is_zero());
+    }
+
+    #[test]
+    fn serialisation_wallet() {
+        let (_, wallet) = owned_wallet(2);
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&wallet]).unwrap();
+
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::Wallet(decoded)) => assert_eq!(decoded, wallet),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn json_wallet() {
+        let (_, wallet) = owned_wallet(2);
+        let obj_after = json::from_str::<Wallet>(&json::to_string(&wallet)).unwrap();
+        assert_eq!(obj_after, wallet);
+        assert_eq!(obj_after.balance().unwrap(), wallet.balance().unwrap());
+
+        // Every coin must still belong to the owner
+        let (_, other_wallet) = owned_wallet(1);
+        let mut json = json::parse(&json::to_string(&wallet)).unwrap();
+        if let Some(coins) = json.as_object_mut().and_then(|object| object.get_mut("coins")) {
+            *coins = vec![other_wallet.coins()[0].clone()].to_json();
+        }
+        match json::from_str::<Wallet>(&json.to_string()) {
+            Err(::Error::NotOwner) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
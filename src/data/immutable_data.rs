@@ -17,7 +17,9 @@
 
 use cbor;
 use cbor::CborTagEncode;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use routing::NameType;
 use routing::sendable::Sendable;
 use sodiumoxide::crypto;
@@ -124,6 +126,19 @@ impl Decodable for ImmutableData {
     }
 }
 
+impl ToJson for ImmutableData {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::IMMUTABLE_DATA_TAG).bytes("value", &self.value).build()
+    }
+}
+
+impl FromJson for ImmutableData {
+    fn from_json(json: &Json) -> Result<ImmutableData, ::Error> {
+        let value = try!(try!(Object::with_tag(json, &[::data_tags::IMMUTABLE_DATA_TAG])).bytes("value"));
+        Ok(ImmutableData::new(value))
+    }
+}
+
 
 /// ImmutableDataBackup
 #[derive(Clone)]
@@ -197,6 +212,19 @@ impl Decodable for ImmutableDataBackup {
     }
 }
 
+impl ToJson for ImmutableDataBackup {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::IMMUTABLE_DATA_BACKUP_TAG).bytes("value", &self.value).build()
+    }
+}
+
+impl FromJson for ImmutableDataBackup {
+    fn from_json(json: &Json) -> Result<ImmutableDataBackup, ::Error> {
+        let value = try!(try!(Object::with_tag(json, &[::data_tags::IMMUTABLE_DATA_BACKUP_TAG])).bytes("value"));
+        Ok(ImmutableDataBackup::new(ImmutableData::new(value)))
+    }
+}
+
 
 /// ImmutableDataSacrificial
 #[derive(Clone)]
@@ -271,6 +299,19 @@ impl Decodable for ImmutableDataSacrificial {
     }
 }
 
+impl ToJson for ImmutableDataSacrificial {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::IMMUTABLE_DATA_SACRIFICIAL_TAG).bytes("value", &self.value).build()
+    }
+}
+
+impl FromJson for ImmutableDataSacrificial {
+    fn from_json(json: &Json) -> Result<ImmutableDataSacrificial, ::Error> {
+        let value = try!(try!(Object::with_tag(json, &[::data_tags::IMMUTABLE_DATA_SACRIFICIAL_TAG])).bytes("value"));
+        Ok(ImmutableDataSacrificial::new(ImmutableData::new(value)))
+    }
+}
+
 
 #[cfg(test)]
 mod test {
@@ -279,6 +320,8 @@ mod test {
     use super::*;
     use self::rand::Rng;
     use cbor::{ Encoder, Decoder};
+    use json;
+    use rustc_serialize::hex::ToHex;
     use rustc_serialize::{Decodable, Encodable};
     use Random;
     use routing::sendable::Sendable;
@@ -338,7 +381,7 @@ mod test {
         let mut immutable_data_decoder =
                 Decoder::from_bytes(immutable_data_encoder.as_bytes());
         match immutable_data_decoder.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::ImmutData(decoded_immutable_data) => assert_eq!(immutable_data, decoded_immutable_data),
+            ::AnyData::ImmutableData(decoded_immutable_data) => assert_eq!(immutable_data, decoded_immutable_data),
             _ => panic!("Unexpected!"),
         }
 
@@ -348,7 +391,7 @@ mod test {
         let mut immutable_data_backup_decoder =
                 Decoder::from_bytes(immutable_data_backup_encoder.as_bytes());
         match immutable_data_backup_decoder.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::ImmutDataBkup(decoded_immutable_data_backup) => assert_eq!(immutable_data_backup, decoded_immutable_data_backup),
+            ::AnyData::ImmutableDataBackup(decoded_immutable_data_backup) => assert_eq!(immutable_data_backup, decoded_immutable_data_backup),
             _ => panic!("Unexpected!"),
         }
 
@@ -358,11 +401,31 @@ mod test {
         let mut immutable_data_sacrificial_decoder =
                 Decoder::from_bytes(immutable_data_sacrificial_encoder.as_bytes());
         match immutable_data_sacrificial_decoder.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::ImmutDataSacrificial(decoded_immutable_data_sacrificial) => assert_eq!(immutable_data_sacrificial, decoded_immutable_data_sacrificial),
+            ::AnyData::ImmutableDataSacrificial(decoded_immutable_data_sacrificial) => assert_eq!(immutable_data_sacrificial, decoded_immutable_data_sacrificial),
             _ => panic!("Unexpected!"),
         }
     }
 
+    #[test]
+    fn json() {
+        let immutable_data = ImmutableData::generate_random();
+        let immutable_data_backup = ImmutableDataBackup::new(immutable_data.clone());
+        let immutable_data_sacrificial = ImmutableDataSacrificial::new(immutable_data.clone());
+
+        let json = json::to_string(&immutable_data);
+        assert_eq!(json::from_str::<ImmutableData>(&json).unwrap(), immutable_data);
+        assert_eq!(json::from_str::<ImmutableDataBackup>(&json::to_string(&immutable_data_backup)).unwrap(),
+                   immutable_data_backup);
+        assert_eq!(json::from_str::<ImmutableDataSacrificial>(&json::to_string(&immutable_data_sacrificial))
+                       .unwrap(), immutable_data_sacrificial);
+
+        // The tag distinguishes the variants
+        assert!(json::from_str::<ImmutableDataBackup>(&json).is_err());
+        let expected = format!("{{\"tag\":{},\"value\":\"{}\"}}", ::data_tags::IMMUTABLE_DATA_TAG,
+                               immutable_data.value().to_hex());
+        assert_eq!(json, expected);
+    }
+
     #[test]
     fn equality() {
         let immutable_data_first = ImmutableData::generate_random();
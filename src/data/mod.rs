@@ -17,9 +17,14 @@
 
 mod structured_data;
 mod immutable_data;
+mod tag_registry;
+/// Self-encryption of large content into ImmutableData chunks
+pub mod self_encryption;
 
 pub use self::structured_data::*;
 pub use self::immutable_data::*;
+pub use self::tag_registry::*;
+pub use self::self_encryption::DataMap;
 
 #[test]
 fn dummy()  {
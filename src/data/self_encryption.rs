@@ -0,0 +1,478 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use cbor::CborTagEncode;
+use json::{FromJson, Object, ObjectBuilder};
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
+use routing::NameType;
+use routing::sendable::Sendable;
+use sodiumoxide::crypto;
+use super::immutable_data::ImmutableData;
+
+/// Content smaller than three times this size is held directly in the DataMap
+pub const MIN_CHUNK_SIZE: usize = 1024;
+/// Upper bound of the size of the content held in each chunk
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Storage for the encrypted chunks produced by `encrypt`
+pub trait ChunkStore {
+    /// Stores the chunk under its name
+    fn put(&mut self, chunk: ImmutableData);
+    /// Returns the chunk stored under name, if any
+    fn get(&self, name: &NameType) -> Option<ImmutableData>;
+}
+
+/// ChunkStore holding the chunks in memory
+pub struct MemoryChunkStore {
+    chunks: HashMap<Vec<u8>, ImmutableData>,
+}
+
+impl MemoryChunkStore {
+    /// Creates an empty store
+    pub fn new() -> MemoryChunkStore {
+        MemoryChunkStore { chunks: HashMap::new() }
+    }
+
+    /// Returns the number of stored chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl ChunkStore for MemoryChunkStore {
+    fn put(&mut self, chunk: ImmutableData) {
+        let _ = self.chunks.insert(chunk.name().0.to_vec(), chunk);
+    }
+
+    fn get(&self, name: &NameType) -> Option<ImmutableData> {
+        self.chunks.get(&name.0.to_vec()).map(|chunk| chunk.clone())
+    }
+}
+
+/// Details of a single encrypted chunk
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChunkDetails {
+    /// Index of the chunk in the original content
+    pub chunk_num: u32,
+    /// Name of the encrypted ImmutableData
+    pub hash: NameType,
+    /// SHA-512 of the unencrypted chunk content
+    pub pre_hash: Vec<u8>,
+    /// Size of the unencrypted chunk content
+    pub source_size: u64,
+}
+
+// A chunk never holds more than MAX_CHUNK_SIZE bytes of content, so a larger source_size can only
+// come from corrupted or crafted details
+fn check_source_size(source_size: u64) -> Result<(), ::Error> {
+    if source_size > MAX_CHUNK_SIZE as u64 {
+        Err(::Error::CorruptedData)
+    } else {
+        Ok(())
+    }
+}
+
+impl Encodable for ChunkDetails {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        (self.chunk_num, &self.hash, &self.pre_hash, self.source_size).encode(e)
+    }
+}
+
+impl Decodable for ChunkDetails {
+    fn decode<D: Decoder>(d: &mut D) -> Result<ChunkDetails, D::Error> {
+        let (chunk_num, hash, pre_hash, source_size): (u32, NameType, Vec<u8>, u64) = try!(Decodable::decode(d));
+        try_decode!(d, ::helper::check_length(pre_hash.len(), crypto::hash::sha512::HASHBYTES));
+        try_decode!(d, check_source_size(source_size));
+        Ok(ChunkDetails { chunk_num: chunk_num, hash: hash, pre_hash: pre_hash, source_size: source_size })
+    }
+}
+
+impl ToJson for ChunkDetails {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::untagged()
+            .field("chunk_num", self.chunk_num)
+            .bytes("hash", &self.hash.0)
+            .bytes("pre_hash", &self.pre_hash)
+            .field("source_size", self.source_size)
+            .build()
+    }
+}
+
+impl FromJson for ChunkDetails {
+    fn from_json(json: &Json) -> Result<ChunkDetails, ::Error> {
+        let object = try!(Object::new(json));
+        let chunk_num = try!(object.u64("chunk_num"));
+        if chunk_num > ::std::u32::MAX as u64 {
+            return Err(::Error::InvalidJson(format!("chunk_num {} is out of range", chunk_num)));
+        }
+        let source_size = try!(object.u64("source_size"));
+        try!(check_source_size(source_size));
+        Ok(ChunkDetails { chunk_num: chunk_num as u32,
+                          hash: try!(object.name_type("hash")),
+                          pre_hash: try!(object.fixed_bytes("pre_hash", crypto::hash::sha512::HASHBYTES)),
+                          source_size: source_size,
+                        })
+    }
+}
+
+/// DataMap
+///
+/// Holds the details required to retrieve and decrypt the chunks of self-encrypted content.
+/// Content too small to be split is held directly instead.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DataMap {
+    chunks: Vec<ChunkDetails>,
+    content: Vec<u8>,
+}
+
+impl DataMap {
+    /// Returns the details of the chunks, empty if the content is held directly
+    pub fn chunks(&self) -> &Vec<ChunkDetails> {
+        &self.chunks
+    }
+
+    /// Returns the content held directly, empty if the content was split into chunks
+    pub fn content(&self) -> &Vec<u8> {
+        &self.content
+    }
+
+    /// Returns the size of the original content, or CorruptedData if the sizes of the chunks
+    /// overflow
+    pub fn len(&self) -> Result<u64, ::Error> {
+        if self.chunks.is_empty() {
+            return Ok(self.content.len() as u64);
+        }
+        let mut total = 0u64;
+        for chunk in self.chunks.iter() {
+            total = try!(total.checked_add(chunk.source_size).ok_or(::Error::CorruptedData));
+        }
+        Ok(total)
+    }
+}
+
+impl Encodable for DataMap {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        CborTagEncode::new(::data_tags::DATA_MAP_TAG, &(&self.chunks, &self.content)).encode(e)
+    }
+}
+
+impl Decodable for DataMap {
+    fn decode<D: Decoder>(d: &mut D) -> Result<DataMap, D::Error> {
+        let (chunks, content) = try!(Decodable::decode(d));
+        Ok(DataMap { chunks: chunks, content: content })
+    }
+}
+
+impl ToJson for DataMap {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::DATA_MAP_TAG)
+            .field("chunks", self.chunks.to_json())
+            .bytes("content", &self.content)
+            .build()
+    }
+}
+
+impl FromJson for DataMap {
+    fn from_json(json: &Json) -> Result<DataMap, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::DATA_MAP_TAG]));
+        let mut chunks = Vec::new();
+        for chunk in try!(object.array("chunks")) {
+            chunks.push(try!(ChunkDetails::from_json(chunk)));
+        }
+        Ok(DataMap { chunks: chunks, content: try!(object.bytes("content")) })
+    }
+}
+
+fn secretbox_key(pre_hash: &[u8]) -> crypto::secretbox::Key {
+    let mut key = [0u8; crypto::secretbox::KEYBYTES];
+    for (key_byte, hash_byte) in key.iter_mut().zip(pre_hash.iter()) {
+        *key_byte = *hash_byte;
+    }
+    crypto::secretbox::Key(key)
+}
+
+fn secretbox_nonce(pre_hash: &[u8]) -> crypto::secretbox::Nonce {
+    let mut nonce = [0u8; crypto::secretbox::NONCEBYTES];
+    for (nonce_byte, hash_byte) in nonce.iter_mut().zip(pre_hash.iter()) {
+        *nonce_byte = *hash_byte;
+    }
+    crypto::secretbox::Nonce(nonce)
+}
+
+// The key of chunk n is taken from the pre-encryption hash of chunk n - 1 and the nonce from
+// that of chunk n - 2, wrapping around at the start of the content.
+fn neighbour_keys(pre_hashes: &[&[u8]], index: usize) -> (crypto::secretbox::Key, crypto::secretbox::Nonce) {
+    let count = pre_hashes.len();
+    (secretbox_key(pre_hashes[(index + count - 1) % count]),
+     secretbox_nonce(pre_hashes[(index + count - 2) % count]))
+}
+
+/// Splits content into encrypted ImmutableData chunks which are put into store and returns the
+/// DataMap needed to rebuild the content
+pub fn encrypt<S: ChunkStore>(content: &[u8], store: &mut S) -> DataMap {
+    if content.len() < 3 * MIN_CHUNK_SIZE {
+        return DataMap { chunks: Vec::new(), content: content.to_vec() };
+    }
+
+    // The first content.len() % chunk_count chunks take one byte more than the rest, so none
+    // exceeds MAX_CHUNK_SIZE
+    let chunk_count = cmp::max(3, (content.len() + MAX_CHUNK_SIZE - 1) / MAX_CHUNK_SIZE);
+    let chunk_size = content.len() / chunk_count;
+    let remainder = content.len() % chunk_count;
+    let chunk_start = |index: usize| index * chunk_size + cmp::min(index, remainder);
+    let sources = (0..chunk_count).map(|index| &content[chunk_start(index)..chunk_start(index + 1)])
+                                  .collect::<Vec<_>>();
+    let pre_hashes = sources.iter().map(|source| crypto::hash::sha512::hash(source).0).collect::<Vec<_>>();
+    let pre_hash_slices = pre_hashes.iter().map(|pre_hash| &pre_hash[..]).collect::<Vec<_>>();
+
+    let mut chunks = Vec::with_capacity(chunk_count);
+    for (index, source) in sources.iter().enumerate() {
+        let (key, nonce) = neighbour_keys(&pre_hash_slices, index);
+        let chunk = ImmutableData::new(crypto::secretbox::seal(source, &nonce, &key));
+        chunks.push(ChunkDetails {
+            chunk_num: index as u32,
+            hash: chunk.name(),
+            pre_hash: pre_hashes[index].to_vec(),
+            source_size: source.len() as u64,
+        });
+        store.put(chunk);
+    }
+    DataMap { chunks: chunks, content: Vec::new() }
+}
+
+/// Rebuilds the original content from the DataMap and the chunks held in store
+pub fn decrypt<S: ChunkStore>(data_map: &DataMap, store: &S) -> Result<Vec<u8>, ::Error> {
+    if data_map.chunks.is_empty() {
+        return Ok(data_map.content.clone());
+    }
+
+    let pre_hash_slices = data_map.chunks.iter().map(|chunk| &chunk.pre_hash[..]).collect::<Vec<_>>();
+    // The sizes in the DataMap are not trusted, so content grows with the chunks actually decrypted
+    let mut content = Vec::new();
+    for (index, details) in data_map.chunks.iter().enumerate() {
+        let chunk = try!(store.get(&details.hash).ok_or(::Error::ChunkNotFound(details.hash.clone())));
+        if chunk.name() != details.hash {
+            return Err(::Error::Decryption);
+        }
+        let (key, nonce) = neighbour_keys(&pre_hash_slices, index);
+        let source = try!(crypto::secretbox::open(chunk.value(), &nonce, &key).ok_or(::Error::Decryption));
+        if crypto::hash::sha512::hash(&source).0.to_vec() != details.pre_hash {
+            return Err(::Error::Decryption);
+        }
+        content.extend(source.into_iter());
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate rand;
+
+    use super::*;
+    use self::rand::Rng;
+    use cbor;
+    use data::ImmutableData;
+    use json;
+    use routing::NameType;
+    use routing::sendable::Sendable;
+
+    // Serves each chunk under its original name, with the first byte of one of them flipped
+    struct TamperingStore {
+        store: MemoryChunkStore,
+        tampered: NameType,
+    }
+
+    impl ChunkStore for TamperingStore {
+        fn put(&mut self, chunk: ImmutableData) {
+            self.store.put(chunk)
+        }
+
+        fn get(&self, name: &NameType) -> Option<ImmutableData> {
+            self.store.get(name).map(|chunk| {
+                if *name != self.tampered {
+                    return chunk;
+                }
+                let mut value = chunk.value().clone();
+                value[0] ^= 1;
+                ImmutableData::new(value)
+            })
+        }
+    }
+
+    fn random_content(size: usize) -> Vec<u8> {
+        rand::thread_rng().gen_iter::<u8>().take(size).collect()
+    }
+
+    #[test]
+    fn small_content() {
+        let content = random_content(3 * MIN_CHUNK_SIZE - 1);
+        let mut store = MemoryChunkStore::new();
+        let data_map = encrypt(&content, &mut store);
+
+        assert!(data_map.chunks().is_empty());
+        assert_eq!(store.len(), 0);
+        assert_eq!(data_map.len().unwrap(), content.len() as u64);
+        assert_eq!(decrypt(&data_map, &store).unwrap(), content);
+    }
+
+    #[test]
+    fn large_content() {
+        let content = random_content(3 * MAX_CHUNK_SIZE + 17);
+        let mut store = MemoryChunkStore::new();
+        let data_map = encrypt(&content, &mut store);
+
+        assert_eq!(data_map.chunks().len(), 4);
+        assert_eq!(store.len(), 4);
+        assert!(data_map.content().is_empty());
+        assert_eq!(data_map.len().unwrap(), content.len() as u64);
+        for chunk in data_map.chunks() {
+            assert!(chunk.source_size as usize <= MAX_CHUNK_SIZE);
+            assert!(store.get(&chunk.hash).is_some());
+        }
+        assert_eq!(decrypt(&data_map, &store).unwrap(), content);
+    }
+
+    #[test]
+    fn chunk_size_limit() {
+        for size in vec![4 * MAX_CHUNK_SIZE - 1, 3 * MAX_CHUNK_SIZE + 2, 3 * MIN_CHUNK_SIZE + 2] {
+            let content = random_content(size);
+            let mut store = MemoryChunkStore::new();
+            let data_map = encrypt(&content, &mut store);
+            let sizes = data_map.chunks().iter().map(|chunk| chunk.source_size as usize).collect::<Vec<_>>();
+            assert!(sizes.iter().all(|size| *size <= MAX_CHUNK_SIZE));
+            assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1);
+            assert_eq!(decrypt(&data_map, &store).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn corrupted_chunk_content() {
+        let content = random_content(3 * MIN_CHUNK_SIZE);
+        let mut store = TamperingStore { store: MemoryChunkStore::new(), tampered: NameType([0u8; 64]) };
+        let data_map = encrypt(&content, &mut store);
+        assert_eq!(decrypt(&data_map, &store).unwrap(), content);
+
+        store.tampered = data_map.chunks()[1].hash.clone();
+        match decrypt(&data_map, &store) {
+            Err(::Error::Decryption) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // A DataMap whose pre-encryption hash has been altered yields the wrong keys
+        store.tampered = NameType([0u8; 64]);
+        let mut altered = data_map.clone();
+        altered.chunks[0].pre_hash[0] ^= 1;
+        match decrypt(&altered, &store) {
+            Err(::Error::Decryption) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn missing_or_tampered_chunk() {
+        let content = random_content(3 * MIN_CHUNK_SIZE);
+        let mut store = MemoryChunkStore::new();
+        let data_map = encrypt(&content, &mut store);
+        assert_eq!(data_map.chunks().len(), 3);
+
+        let mut tampered_store = MemoryChunkStore::new();
+        for (index, chunk) in data_map.chunks().iter().enumerate() {
+            let mut value = store.get(&chunk.hash).unwrap().value().clone();
+            if index == 1 {
+                value[0] ^= 1;
+            }
+            tampered_store.put(ImmutableData::new(value));
+        }
+        match decrypt(&data_map, &tampered_store) {
+            Err(::Error::ChunkNotFound(name)) => assert_eq!(name, data_map.chunks()[1].hash),
+            _ => panic!("Unexpected!"),
+        }
+
+        let empty_store = MemoryChunkStore::new();
+        assert!(decrypt(&data_map, &empty_store).is_err());
+    }
+
+    #[test]
+    fn crafted_sizes() {
+        let content = random_content(3 * MIN_CHUNK_SIZE);
+        let mut store = MemoryChunkStore::new();
+        let data_map = encrypt(&content, &mut store);
+
+        // Sizes which could only add up by overflowing are reported rather than summed
+        let mut overflowing = data_map.clone();
+        for chunk in overflowing.chunks.iter_mut() {
+            chunk.source_size = ::std::u64::MAX / 2;
+        }
+        match overflowing.len() {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // and are refused on decode, so decrypt never sees them
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&overflowing]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match json::from_str::<DataMap>(&json::to_string(&overflowing)) {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert_eq!(decrypt(&overflowing, &store).unwrap(), content);
+
+        let mut oversized = data_map.clone();
+        oversized.chunks[0].source_size = MAX_CHUNK_SIZE as u64 + 1;
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&oversized]).unwrap();
+        assert!(::AnyData::from_bytes(e.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn serialisation_data_map() {
+        let content = random_content(3 * MIN_CHUNK_SIZE + 5);
+        let mut store = MemoryChunkStore::new();
+        let data_map = encrypt(&content, &mut store);
+
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&data_map]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::DataMap(decoded)) => {
+                assert_eq!(data_map, decoded);
+                assert_eq!(decrypt(&decoded, &store).unwrap(), content);
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn json_data_map() {
+        let mut store = MemoryChunkStore::new();
+        for size in vec![3 * MIN_CHUNK_SIZE + 5, 10] {
+            let content = random_content(size);
+            let data_map = encrypt(&content, &mut store);
+            let obj_after = json::from_str::<DataMap>(&json::to_string(&data_map)).unwrap();
+            assert_eq!(data_map, obj_after);
+            assert_eq!(decrypt(&obj_after, &store).unwrap(), content);
+        }
+        assert!(json::from_str::<DataMap>(&json::to_string(&ImmutableData::new(vec![1u8]))).is_err());
+    }
+}
@@ -15,15 +15,23 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use std::cmp;
-
 use cbor;
 use cbor::CborTagEncode;
+use json;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use routing::NameType;
 use routing::sendable::Sendable;
+use sodiumoxide::crypto;
+use std::fmt;
+use helper::*;
+use signable::{Signable, SignableBytes};
 use TypeTag;
 
+/// Maximum size in bytes of the data held by a StructuredData
+pub const MAX_STRUCTURED_DATA_SIZE: usize = 100 * 1024;
+
 /// TypeTag for StructuredData
 #[derive(Clone, PartialEq, Debug)]
 pub struct StructuredDataTypeTag;
@@ -34,13 +42,80 @@ impl TypeTag for StructuredDataTypeTag {
     }
 }
 
+/// Result of checking the signatures of a StructuredData against a set of owners
+#[derive(Clone)]
+pub struct SignatureReport {
+    /// Owners whose signature is present and valid
+    pub valid: Vec<crypto::sign::PublicKey>,
+    /// Owners whose signature is present but does not verify
+    pub invalid: Vec<crypto::sign::PublicKey>,
+    /// Owners who have not signed
+    pub missing: Vec<crypto::sign::PublicKey>,
+}
+
+impl SignatureReport {
+    /// Returns the number of valid signatures needed: more than half of the owners
+    pub fn required(&self) -> usize {
+        (self.valid.len() + self.invalid.len() + self.missing.len()) / 2 + 1
+    }
+
+    /// Returns true if more than half of the owners have validly signed
+    pub fn has_majority(&self) -> bool {
+        self.valid.len() >= self.required()
+    }
+}
+
+impl fmt::Debug for SignatureReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SignatureReport {{ valid:{:?}, invalid:{:?}, missing:{:?} }}",
+            self.valid.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>(),
+            self.invalid.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>(),
+            self.missing.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>())
+    }
+}
+
 /// StructuredData
-#[derive(Clone, PartialEq, Debug)]
+///
+/// Applications distinguish their kinds of StructuredData by a `type_tag` outside the ranges
+/// reserved in `data_tags` (see `TagRegistry`).  The name is derived from the identifier and that
+/// type tag, so the same identifier may be used for different kinds of data.
+///
+/// Each mutation of StructuredData produces a successor with the next version number.  More than
+/// half of the owners of the current version must sign the successor, whether it changes the
+/// data or transfers ownership.  Vaults check this with `validate_successor` before replacing
+/// the stored copy.
+///
+/// #Examples
+///
+/// ```
+/// extern crate sodiumoxide;
+/// extern crate routing;
+/// extern crate maidsafe_types;
+/// use maidsafe_types::StructuredData;
+///
+/// # fn main() {
+/// let (public_key, secret_key) = sodiumoxide::crypto::sign::gen_keypair();
+/// let type_tag = 1000;
+/// let identifier = routing::NameType([1u8; 64]);
+/// let mut current = StructuredData::new(type_tag, identifier.clone(), 0, vec![public_key], vec![]).unwrap();
+/// current.add_signature(&public_key, &secret_key);
+///
+/// let mut successor = StructuredData::new(type_tag, identifier, 1, vec![public_key], b"new data".to_vec()).unwrap();
+/// successor.add_signature(&public_key, &secret_key);
+/// assert!(current.validate_successor(&successor).is_ok());
+/// # }
+/// ```
+#[derive(Clone)]
 pub struct StructuredData {
     type_tag: StructuredDataTypeTag,
+    data_type_tag: u64,
+    identifier: NameType,
     name: NameType,
-    owner: NameType,
-    value: Vec<NameType>,
+    version: u64,
+    owners: Vec<crypto::sign::PublicKey>,
+    data: Vec<u8>,
+    max_size: usize,
+    signatures: Vec<(crypto::sign::PublicKey, crypto::sign::Signature)>,
 }
 
 impl Sendable for StructuredData {
@@ -59,7 +134,7 @@ impl Sendable for StructuredData {
     }
 
     fn owner(&self) -> Option<NameType> {
-        Some(self.owner.clone())
+        self.owners.first().map(|key| NameType(crypto::hash::sha512::hash(&key.0).0))
     }
 
     fn refresh(&self)->bool {
@@ -67,63 +142,301 @@ impl Sendable for StructuredData {
     }
 
     fn merge(&self, sdvs: Vec<Box<Sendable>>) -> Option<Box<Sendable>> {
-        let mut merged_value = self.value.clone();
+        // Signed content can't be combined, so a copy is only returned if every copy held under
+        // the same name agrees with this one
         for itr in sdvs {
             let mut d_sdv = cbor::Decoder::from_bytes(&itr.serialised_contents()[..]);
-            let sdv: StructuredData = d_sdv.decode().next().unwrap().unwrap();
-            if sdv.name() == self.name() {
-                let mut merging = Vec::new();
-                let incoming_value = sdv.value();
-                for i in 0..cmp::min(merged_value.len(), incoming_value.len()) {
-                    if merged_value[i] == incoming_value[i] {
-                        merging.push(merged_value[i].clone());
-                    } else {
-                        break;
-                    }
-                }
-                merged_value = merging;
+            let sdv: StructuredData = match d_sdv.decode().next() {
+                Some(Ok(sdv)) => sdv,
+                _ => return None,
+            };
+            if sdv.name() == self.name() && sdv != *self {
+                return None;
             }
         }
-        if merged_value.len() == 0 {
-            None
-        } else {
-            Some(Box::new(StructuredData::new(self.name.clone(), self.owner.clone(), merged_value)))
+        Some(Box::new(self.clone()))
+    }
+}
+
+impl PartialEq for StructuredData {
+    fn eq(&self, other: &StructuredData) -> bool {
+        self.type_tag == other.type_tag &&
+        self.data_type_tag == other.data_type_tag &&
+        self.identifier == other.identifier &&
+        self.name == other.name &&
+        self.version == other.version &&
+        self.owners.len() == other.owners.len() &&
+        self.owners.iter().zip(other.owners.iter()).all(|(lhs, rhs)| slice_equal(&lhs.0, &rhs.0)) &&
+        self.data == other.data &&
+        self.max_size == other.max_size &&
+        self.signatures.len() == other.signatures.len() &&
+        self.signatures.iter().zip(other.signatures.iter()).all(|(lhs, rhs)| {
+            slice_equal(&(lhs.0).0, &(rhs.0).0) && slice_equal(&(lhs.1).0, &(rhs.1).0)
+        })
+    }
+}
+
+impl fmt::Debug for StructuredData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StructuredData {{ type_tag:{}, identifier:{:?}, name:{:?}, version:{}, owners:{:?}, data:{:?}, max_size:{}, signatures:{:?} }}",
+            self.data_type_tag, self.identifier, self.name, self.version,
+            self.owners.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>(),
+            self.data, self.max_size,
+            self.signatures.iter().map(|&(ref key, ref signature)| (key.0.to_vec(), signature.0.to_vec()))
+                               .collect::<Vec<_>>())
+    }
+}
+
+// Each owner may be listed only once, so that no signature is counted twice towards a majority
+fn check_owners(owners: &[crypto::sign::PublicKey]) -> Result<(), ::Error> {
+    for (index, owner) in owners.iter().enumerate() {
+        if owners[..index].iter().any(|earlier| slice_equal(&earlier.0, &owner.0)) {
+            return Err(::Error::DuplicateOwner);
         }
     }
+    Ok(())
+}
+
+// The limit held by a StructuredData may lower MAX_STRUCTURED_DATA_SIZE but never raise it, so that
+// a limit read from untrusted bytes still bounds the data
+fn check_max_size(max_size: u64) -> Result<usize, ::Error> {
+    if max_size > MAX_STRUCTURED_DATA_SIZE as u64 {
+        let actual = if max_size > ::std::usize::MAX as u64 { ::std::usize::MAX } else { max_size as usize };
+        return Err(::Error::DataTooLarge { max: MAX_STRUCTURED_DATA_SIZE, actual: actual });
+    }
+    Ok(max_size as usize)
 }
 
 impl StructuredData {
     /// An instance of the StructuredData can be created by invoking the new()
-    pub fn new(name: NameType, owner: NameType, value: Vec<NameType>) -> StructuredData {
-        StructuredData {type_tag: StructuredDataTypeTag, name: name, owner: owner, value: value}
+    /// The returned instance holds no signatures; see `add_signature`
+    /// Returns DuplicateOwner if a key appears more than once in owners, or DataTooLarge if data
+    /// exceeds MAX_STRUCTURED_DATA_SIZE
+    pub fn new(type_tag: u64, identifier: NameType, version: u64, owners: Vec<crypto::sign::PublicKey>,
+               data: Vec<u8>) -> Result<StructuredData, ::Error> {
+        StructuredData::new_with_max_size(type_tag, identifier, version, owners, data, MAX_STRUCTURED_DATA_SIZE)
+    }
+
+    /// As new(), but data is limited to max_size bytes rather than MAX_STRUCTURED_DATA_SIZE.  The
+    /// limit is held by the StructuredData, signed by its owners and checked by `set_data` and on
+    /// decode; vaults enforcing a limit of their own use `check_size`.  Returns DataTooLarge if
+    /// max_size exceeds MAX_STRUCTURED_DATA_SIZE.
+    pub fn new_with_max_size(type_tag: u64, identifier: NameType, version: u64,
+                             owners: Vec<crypto::sign::PublicKey>, data: Vec<u8>,
+                             max_size: usize) -> Result<StructuredData, ::Error> {
+        try!(check_owners(&owners));
+        let max_size = try!(check_max_size(max_size as u64));
+        let structured_data = StructuredData {
+            type_tag: StructuredDataTypeTag,
+            data_type_tag: type_tag,
+            name: StructuredData::compute_name(type_tag, &identifier),
+            identifier: identifier,
+            version: version,
+            owners: owners,
+            data: data,
+            max_size: max_size,
+            signatures: Vec::new(),
+        };
+        try!(structured_data.check_size(max_size));
+        Ok(structured_data)
+    }
+
+    /// Returns the name of the StructuredData with the given type tag and identifier
+    pub fn compute_name(type_tag: u64, identifier: &NameType) -> NameType {
+        let mut combined = identifier.0.to_vec();
+        combined.extend(type_tag.to_string().into_bytes().into_iter());
+        NameType(crypto::hash::sha512::hash(&combined).0)
     }
 
-    /// Returns the value
-    pub fn value(&self) -> Vec<NameType> {
-        self.value.clone()
+    /// Returns the application defined type tag
+    pub fn data_type_tag(&self) -> u64 {
+        self.data_type_tag
     }
 
-    /// Sets the value
-    pub fn set_value(&mut self, data: Vec<NameType>) {
-        self.value = data;
+    /// Returns the identifier from which the name is derived
+    pub fn identifier(&self) -> &NameType {
+        &self.identifier
+    }
+
+    /// Returns the version
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the signing public keys of the owners
+    pub fn owners(&self) -> &Vec<crypto::sign::PublicKey> {
+        &self.owners
+    }
+
+    /// Returns the data
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    /// Returns the maximum size in bytes of the data
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Sets the data, discarding any signatures made over the previous data
+    /// Returns DataTooLarge and leaves self unchanged if data exceeds max_size()
+    pub fn set_data(&mut self, data: Vec<u8>) -> Result<(), ::Error> {
+        if data.len() > self.max_size {
+            return Err(::Error::DataTooLarge { max: self.max_size, actual: data.len() });
+        }
+        self.data = data;
+        self.signatures.clear();
+        Ok(())
+    }
+
+    /// Returns DataTooLarge if the data exceeds max_size bytes
+    pub fn check_size(&self, max_size: usize) -> Result<(), ::Error> {
+        if self.data.len() > max_size {
+            Err(::Error::DataTooLarge { max: max_size, actual: self.data.len() })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the signatures, each with the public key of its signer
+    pub fn signatures(&self) -> &Vec<(crypto::sign::PublicKey, crypto::sign::Signature)> {
+        &self.signatures
+    }
+
+    /// Signs the signable_bytes with the secret key, replacing any earlier signature by the same
+    /// public key
+    pub fn add_signature(&mut self, public_key: &crypto::sign::PublicKey,
+                         secret_key: &crypto::sign::SecretKey) {
+        let signature = crypto::sign::sign_detached(&self.signable_bytes(), secret_key);
+        self.signatures.retain(|&(ref key, _)| !slice_equal(&key.0, &public_key.0));
+        self.signatures.push((public_key.clone(), signature));
+    }
+
+    /// Reports which of the owners have signed the signable_bytes.  Signatures from keys which are
+    /// not among the owners are ignored, and an owner listed more than once is reported once.
+    pub fn verify_owner_signatures(&self, owners: &[crypto::sign::PublicKey]) -> SignatureReport {
+        let data = self.signable_bytes();
+        let mut report = SignatureReport { valid: Vec::new(), invalid: Vec::new(), missing: Vec::new() };
+        for (index, owner) in owners.iter().enumerate() {
+            if owners[..index].iter().any(|earlier| slice_equal(&earlier.0, &owner.0)) {
+                continue;
+            }
+            let signature = self.signatures.iter().find(|&&(ref key, _)| slice_equal(&key.0, &owner.0));
+            match signature {
+                Some(&(_, ref signature)) => {
+                    if crypto::sign::verify_detached(signature, &data, owner) {
+                        report.valid.push(owner.clone());
+                    } else {
+                        report.invalid.push(owner.clone());
+                    }
+                },
+                None => report.missing.push(owner.clone()),
+            }
+        }
+        report
+    }
+
+    /// Checks that new may replace self: same name, next version and signed by more than half of
+    /// the owners of self.  Data at the last version, u64::MAX, has no successor.
+    pub fn validate_successor(&self, new: &StructuredData) -> Result<(), ::Error> {
+        if new.name != self.name {
+            return Err(::Error::NameMismatch);
+        }
+        match self.version.checked_add(1) {
+            Some(expected) if expected == new.version => (),
+            Some(expected) => return Err(::Error::InvalidVersion { expected: expected, actual: new.version }),
+            None => return Err(::Error::InvalidVersion { expected: self.version, actual: new.version }),
+        }
+        let report = new.verify_owner_signatures(&self.owners);
+        if !report.has_majority() {
+            return Err(::Error::InsufficientSignatures { required: report.required(), actual: report.valid.len() });
+        }
+        Ok(())
+    }
+}
+
+/// Signs "StructuredData", type tag, identifier, version, list of owners, data and maximum size of
+/// the data
+impl Signable for StructuredData {
+    fn signable_bytes(&self) -> Vec<u8> {
+        SignableBytes::new("StructuredData").u64(self.data_type_tag).bytes(&self.identifier.0)
+            .u64(self.version).public_keys(&self.owners).bytes(&self.data).u64(self.max_size as u64)
+            .into_bytes()
     }
 }
 
 impl Encodable for StructuredData {
     fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
-        CborTagEncode::new(::data_tags::STRUCTURED_DATA_TAG, &(&self.name, &self.owner, &self.value)).encode(e)
+        let owner_key_vecs = self.owners.iter().map(|key| key.0.to_vec()).collect::<Vec<_>>();
+        let signer_key_vecs = self.signatures.iter().map(|&(ref key, _)| key.0.to_vec()).collect::<Vec<_>>();
+        let signature_vecs = self.signatures.iter().map(|&(_, ref signature)| signature.0.to_vec()).collect::<Vec<_>>();
+        CborTagEncode::new(::data_tags::STRUCTURED_DATA_TAG,
+                           &(self.data_type_tag, &self.identifier, self.version, owner_key_vecs,
+                             &self.data, self.max_size as u64, signer_key_vecs, signature_vecs)).encode(e)
     }
 }
 
 impl Decodable for StructuredData {
     fn decode<D: Decoder>(d: &mut D) -> Result<StructuredData, D::Error> {
-        let (name, owner, value) = try!(Decodable::decode(d));
+        let (data_type_tag, identifier, version, owner_key_vecs, data, max_size, signer_key_vecs, signature_vecs):
+            (u64, NameType, u64, Vec<Vec<u8>>, Vec<u8>, u64, Vec<Vec<u8>>, Vec<Vec<u8>>) = try!(Decodable::decode(d));
+        let signer_keys = try_decode!(d, vecs_to_public_keys(signer_key_vecs));
+        let signatures = try_decode!(d, vecs_to_signatures(signature_vecs));
+        if signer_keys.len() != signatures.len() {
+            return Err(d.error("Mismatched StructuredData signer keys and signatures"));
+        }
+        let owners = try_decode!(d, vecs_to_public_keys(owner_key_vecs));
+        try_decode!(d, check_owners(&owners));
+        let max_size = try_decode!(d, check_max_size(max_size));
+        let structured = StructuredData {
+            type_tag: StructuredDataTypeTag,
+            data_type_tag: data_type_tag,
+            name: StructuredData::compute_name(data_type_tag, &identifier),
+            identifier: identifier,
+            version: version,
+            owners: owners,
+            data: data,
+            max_size: max_size,
+            signatures: signer_keys.into_iter().zip(signatures.into_iter()).collect(),
+        };
+        try_decode!(d, structured.check_size(structured.max_size));
+        Ok(structured)
+    }
+}
+
+impl ToJson for StructuredData {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::STRUCTURED_DATA_TAG)
+            .field("type_tag", self.data_type_tag)
+            .bytes("identifier", &self.identifier.0)
+            .field("version", self.version)
+            .field("owners", json::public_keys(&self.owners))
+            .bytes("data", &self.data)
+            .field("max_size", self.max_size as u64)
+            .field("signatures", json::signatures(&self.signatures))
+            .build()
+    }
+}
+
+impl FromJson for StructuredData {
+    fn from_json(json: &Json) -> Result<StructuredData, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::STRUCTURED_DATA_TAG]));
+        let data_type_tag = try!(object.u64("type_tag"));
+        let identifier = try!(object.name_type("identifier"));
+        let owners = try!(object.public_keys("owners"));
+        try!(check_owners(&owners));
         let structured = StructuredData {
             type_tag: StructuredDataTypeTag,
-            name: name,
-            owner: owner,
-            value: value
+            data_type_tag: data_type_tag,
+            name: StructuredData::compute_name(data_type_tag, &identifier),
+            identifier: identifier,
+            version: try!(object.u64("version")),
+            owners: owners,
+            data: try!(object.bytes("data")),
+            max_size: try!(check_max_size(try!(object.u64("max_size")))),
+            signatures: try!(object.signatures("signatures")),
         };
+        try!(structured.check_size(structured.max_size));
         Ok(structured)
     }
 }
@@ -132,35 +445,52 @@ mod test {
     extern crate rand;
 
     use super::*;
+    use self::rand::Rng;
     use cbor::{ Encoder, Decoder };
+    use json;
     use rustc_serialize::{Decodable, Encodable};
     use routing;
     use routing::NameType;
     use routing::sendable::Sendable;
+    use sodiumoxide::crypto;
+    use helper::slice_equal;
+    use rustc_serialize::hex::ToHex;
+    use signable::Signable;
+    use signable::expected::{field, number};
+    use std::u64;
     use Random;
 
+    const TYPE_TAG: u64 = 1000;
+
+    fn random_data() -> Vec<u8> {
+        let size = rand::random::<usize>() % 1024 + 1;
+        rand::thread_rng().gen_iter::<u8>().take(size).collect()
+    }
+
     impl Random for StructuredData {
         fn generate_random() -> StructuredData {
-            let size = rand::random::<usize>() % 100 + 1;
-            let mut value = Vec::<NameType>::with_capacity(size);
-            for _ in 0..size {
-                value.push(routing::test_utils::Random::generate_random());
-            }
-            StructuredData {
-                type_tag: StructuredDataTypeTag,
-                name: routing::test_utils::Random::generate_random(),
-                owner: routing::test_utils::Random::generate_random(),
-                value: value,
-            }
+            let (public_key, secret_key) = crypto::sign::gen_keypair();
+            let mut structured_data = StructuredData::new(TYPE_TAG,
+                                                          routing::test_utils::Random::generate_random(),
+                                                          rand::random::<u64>() % 100,
+                                                          vec![public_key],
+                                                          random_data()).unwrap();
+            structured_data.add_signature(&public_key, &secret_key);
+            structured_data
         }
     }
 
 #[test]
     fn creation() {
         let structured_data = StructuredData::generate_random();
-        let data = StructuredData::new(structured_data.name(), structured_data.owner().unwrap(), structured_data.value());
+        let mut data = StructuredData::new(structured_data.data_type_tag(), structured_data.identifier().clone(),
+                                           structured_data.version(),
+                                           structured_data.owners().clone(), structured_data.data().clone()).unwrap();
+        assert!(data != structured_data);
+        data.signatures = structured_data.signatures().clone();
         assert_eq!(data, structured_data);
         assert_eq!(structured_data.type_tag(), ::data_tags::STRUCTURED_DATA_TAG);
+        assert!(structured_data.verify_owner_signatures(structured_data.owners()).has_majority());
     }
 
 #[test]
@@ -174,7 +504,7 @@ mod test {
 
         let mut d = Decoder::from_bytes(e.as_bytes());
         match d.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::StructData(obj_after) => {
+            ::AnyData::StructuredData(obj_after) => {
                 assert_eq!(obj_before, obj_after);
                 assert!(!(obj_before != obj_before_clone));
                 assert!(obj_before != obj_before1);
@@ -182,4 +512,315 @@ mod test {
             _ => panic!("Unexpected!"),
         }
     }
+
+#[test]
+    fn json_structured_data() {
+        let obj_before = StructuredData::generate_random();
+        let mut e = Encoder::from_memory();
+        e.encode(&[&obj_before]).unwrap();
+
+        let obj_after = json::from_str::<StructuredData>(&json::to_string(&obj_before)).unwrap();
+        assert_eq!(obj_before, obj_after);
+        assert!(obj_after.verify_owner_signatures(obj_after.owners()).has_majority());
+        // Lossless: the CBOR encoding is unchanged by the round trip
+        let mut e_after = Encoder::from_memory();
+        e_after.encode(&[&obj_after]).unwrap();
+        assert_eq!(e.as_bytes(), e_after.as_bytes());
+
+        assert!(json::from_str::<StructuredData>(&json::to_string(&::ImmutableData::new(vec![1u8]))).is_err());
+        assert!(json::from_str::<StructuredData>("{\"tag\":5483104}").is_err());
+    }
+
+#[test]
+    fn validate_successor() {
+        let (owner_public_key, owner_secret_key) = crypto::sign::gen_keypair();
+        let (other_public_key, other_secret_key) = crypto::sign::gen_keypair();
+        let identifier: NameType = routing::test_utils::Random::generate_random();
+        let mut current = StructuredData::new(TYPE_TAG, identifier.clone(), 3, vec![owner_public_key], random_data()).unwrap();
+        current.add_signature(&owner_public_key, &owner_secret_key);
+
+        // Ownership transfer signed by the current owner
+        let mut successor = StructuredData::new(TYPE_TAG, identifier.clone(), 4, vec![other_public_key], random_data()).unwrap();
+        successor.add_signature(&owner_public_key, &owner_secret_key);
+        assert!(current.validate_successor(&successor).is_ok());
+
+        // Signed by someone other than the current owner
+        let mut forged = StructuredData::new(TYPE_TAG, identifier.clone(), 4, vec![other_public_key], random_data()).unwrap();
+        forged.add_signature(&other_public_key, &other_secret_key);
+        match current.validate_successor(&forged) {
+            Err(::Error::InsufficientSignatures { required, actual }) => {
+                assert_eq!(required, 1);
+                assert_eq!(actual, 0);
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        // Unsigned, or modified after signing
+        let mut tampered = successor.clone();
+        tampered.data.push(0);
+        assert!(current.validate_successor(&tampered).is_err());
+        tampered.set_data(random_data()).unwrap();
+        assert!(tampered.signatures().is_empty());
+        assert!(current.validate_successor(&tampered).is_err());
+
+        // Wrong version
+        let mut stale = StructuredData::new(TYPE_TAG, identifier.clone(), 3, vec![owner_public_key], random_data()).unwrap();
+        stale.add_signature(&owner_public_key, &owner_secret_key);
+        match current.validate_successor(&stale) {
+            Err(::Error::InvalidVersion { expected, actual }) => {
+                assert_eq!(expected, 4);
+                assert_eq!(actual, 3);
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        // No version follows the last one
+        let mut last = current.clone();
+        last.version = u64::MAX;
+        let mut wrapped = StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![owner_public_key], random_data()).unwrap();
+        wrapped.add_signature(&owner_public_key, &owner_secret_key);
+        match last.validate_successor(&wrapped) {
+            Err(::Error::InvalidVersion { expected, actual }) => {
+                assert_eq!(expected, u64::MAX);
+                assert_eq!(actual, 0);
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        // Different name
+        let mut renamed = StructuredData::new(TYPE_TAG + 1, identifier.clone(), 4,
+                                              vec![owner_public_key], random_data()).unwrap();
+        renamed.add_signature(&owner_public_key, &owner_secret_key);
+        match current.validate_successor(&renamed) {
+            Err(::Error::NameMismatch) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+#[test]
+    fn threshold_signatures() {
+        let keys = (0..4).map(|_| crypto::sign::gen_keypair()).collect::<Vec<_>>();
+        let owners = keys.iter().map(|&(ref public_key, _)| public_key.clone()).collect::<Vec<_>>();
+        let identifier: NameType = routing::test_utils::Random::generate_random();
+        let current = StructuredData::new(TYPE_TAG, identifier.clone(), 0, owners.clone(), random_data()).unwrap();
+
+        let mut successor = StructuredData::new(TYPE_TAG, identifier.clone(), 1, owners.clone(), random_data()).unwrap();
+        successor.add_signature(&keys[0].0, &keys[0].1);
+        successor.add_signature(&keys[1].0, &keys[1].1);
+        // Half of the owners is not enough
+        match current.validate_successor(&successor) {
+            Err(::Error::InsufficientSignatures { required, actual }) => {
+                assert_eq!(required, 3);
+                assert_eq!(actual, 2);
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        // A signature claimed by an owner but made with another key is reported as invalid
+        let forged_signature = crypto::sign::sign_detached(&successor.signable_bytes(), &keys[3].1);
+        successor.signatures.push((keys[2].0.clone(), forged_signature));
+        let report = successor.verify_owner_signatures(&owners);
+        assert_eq!(report.valid.len(), 2);
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.missing.len(), 1);
+        assert!(slice_equal(&report.invalid[0].0, &keys[2].0 .0));
+        assert!(slice_equal(&report.missing[0].0, &keys[3].0 .0));
+        assert!(!report.has_majority());
+
+        successor.add_signature(&keys[2].0, &keys[2].1);
+        let report = successor.verify_owner_signatures(&owners);
+        assert_eq!(report.valid.len(), 3);
+        assert!(report.invalid.is_empty());
+        assert!(report.has_majority());
+        assert!(current.validate_successor(&successor).is_ok());
+
+        // Ownership transfer to a single new owner still needs the majority of the current owners
+        let (new_owner, _) = crypto::sign::gen_keypair();
+        let mut transfer = StructuredData::new(TYPE_TAG, identifier.clone(), 1, vec![new_owner], random_data()).unwrap();
+        transfer.add_signature(&keys[3].0, &keys[3].1);
+        assert!(current.validate_successor(&transfer).is_err());
+        transfer.add_signature(&keys[0].0, &keys[0].1);
+        transfer.add_signature(&keys[1].0, &keys[1].1);
+        assert!(current.validate_successor(&transfer).is_ok());
+    }
+
+    #[test]
+    fn duplicate_owners() {
+        let (owner_a, secret_a) = crypto::sign::gen_keypair();
+        let (owner_b, _) = crypto::sign::gen_keypair();
+        let identifier = NameType([2u8; 64]);
+        match StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![owner_a, owner_a, owner_b], vec![]) {
+            Err(::Error::DuplicateOwner) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // A single signature is not a majority of [A, B] however often A is listed
+        let mut successor = StructuredData::new(TYPE_TAG, identifier.clone(), 1, vec![owner_a], vec![]).unwrap();
+        successor.add_signature(&owner_a, &secret_a);
+        let report = successor.verify_owner_signatures(&[owner_a, owner_a, owner_b]);
+        assert_eq!(report.valid.len(), 1);
+        assert_eq!(report.required(), 2);
+        assert!(!report.has_majority());
+
+        // Nor can duplicates be introduced through decoding
+        let mut duplicated = StructuredData::new(TYPE_TAG, identifier, 0, vec![owner_a, owner_b], vec![]).unwrap();
+        duplicated.owners[1] = owner_a;
+        let mut e = Encoder::from_memory();
+        e.encode(&[&duplicated]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::DuplicateOwner) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(json::from_str::<StructuredData>(&json::to_string(&duplicated)).is_err());
+    }
+
+#[test]
+    fn data_size_limit() {
+        let (public_key, secret_key) = crypto::sign::gen_keypair();
+        let identifier: NameType = routing::test_utils::Random::generate_random();
+        assert!(StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![0u8; MAX_STRUCTURED_DATA_SIZE]).is_ok());
+        match StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![0u8; MAX_STRUCTURED_DATA_SIZE + 1]) {
+            Err(::Error::DataTooLarge { max, actual }) => {
+                assert_eq!(max, MAX_STRUCTURED_DATA_SIZE);
+                assert_eq!(actual, MAX_STRUCTURED_DATA_SIZE + 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+        assert!(StructuredData::new_with_max_size(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![0u8; 11], 10).is_err());
+
+        let mut structured_data = StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![1u8; 10]).unwrap();
+        structured_data.add_signature(&public_key, &secret_key);
+        assert!(structured_data.set_data(vec![0u8; MAX_STRUCTURED_DATA_SIZE + 1]).is_err());
+        assert_eq!(structured_data.data(), &vec![1u8; 10]);
+        assert_eq!(structured_data.signatures().len(), 1);
+
+        // Oversized data is rejected on decode
+        structured_data.data = vec![0u8; MAX_STRUCTURED_DATA_SIZE + 1];
+        let mut e = Encoder::from_memory();
+        e.encode(&[&structured_data]).unwrap();
+        assert!(::AnyData::from_bytes(e.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn own_size_limit() {
+        let (public_key, secret_key) = crypto::sign::gen_keypair();
+        let identifier = NameType([4u8; 64]);
+        let small = 10;
+        let mut structured_data = StructuredData::new_with_max_size(TYPE_TAG, identifier.clone(), 0, vec![public_key],
+                                                                    vec![1u8; small], small).unwrap();
+        assert_eq!(structured_data.max_size(), small);
+        match structured_data.set_data(vec![2u8; small + 1]) {
+            Err(::Error::DataTooLarge { max, actual }) => {
+                assert_eq!(max, small);
+                assert_eq!(actual, small + 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+        structured_data.add_signature(&public_key, &secret_key);
+
+        let mut e = Encoder::from_memory();
+        e.encode(&[&structured_data]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::StructuredData(decoded)) => {
+                assert_eq!(decoded, structured_data);
+                assert_eq!(decoded.verify_owner_signatures(&[public_key]).valid.len(), 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+        assert_eq!(json::from_str::<StructuredData>(&json::to_string(&structured_data)).unwrap(), structured_data);
+
+        // Lowering the limit is signed by the owners like any other change
+        let mut lowered = structured_data.clone();
+        lowered.max_size = small - 1;
+        assert!(lowered.verify_owner_signatures(&[public_key]).valid.is_empty());
+
+        // The limit cannot be raised above MAX_STRUCTURED_DATA_SIZE
+        match StructuredData::new_with_max_size(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![],
+                                                MAX_STRUCTURED_DATA_SIZE + 1) {
+            Err(::Error::DataTooLarge { max, actual }) => {
+                assert_eq!(max, MAX_STRUCTURED_DATA_SIZE);
+                assert_eq!(actual, MAX_STRUCTURED_DATA_SIZE + 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn oversized_declared_limit() {
+        let (public_key, _) = crypto::sign::gen_keypair();
+        let mut structured_data = StructuredData::new(TYPE_TAG, NameType([4u8; 64]), 0, vec![public_key], vec![]).unwrap();
+        structured_data.max_size = ::std::usize::MAX;
+        structured_data.data = vec![1u8; MAX_STRUCTURED_DATA_SIZE + 1];
+
+        let mut e = Encoder::from_memory();
+        e.encode(&[&structured_data]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::DataTooLarge { max, actual }) => {
+                assert_eq!(max, MAX_STRUCTURED_DATA_SIZE);
+                assert_eq!(actual, ::std::usize::MAX);
+            },
+            _ => panic!("Unexpected!"),
+        }
+        match json::from_str::<StructuredData>(&json::to_string(&structured_data)) {
+            Err(::Error::DataTooLarge { max, .. }) => assert_eq!(max, MAX_STRUCTURED_DATA_SIZE),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+#[test]
+    fn name_from_type_tag_and_identifier() {
+        let (public_key, _) = crypto::sign::gen_keypair();
+        let identifier: NameType = routing::test_utils::Random::generate_random();
+        let contacts = StructuredData::new(TYPE_TAG, identifier.clone(), 0, vec![public_key], vec![]).unwrap();
+        let dns = StructuredData::new(TYPE_TAG + 1, identifier.clone(), 0, vec![public_key], vec![]).unwrap();
+
+        assert!(contacts.name() != dns.name());
+        assert_eq!(contacts.name(), StructuredData::compute_name(TYPE_TAG, &identifier));
+        assert_eq!(contacts.data_type_tag(), TYPE_TAG);
+        assert_eq!(contacts.identifier(), &identifier);
+        assert_eq!(contacts.type_tag(), ::data_tags::STRUCTURED_DATA_TAG);
+
+        let mut e = Encoder::from_memory();
+        e.encode(&[&dns]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::StructuredData(decoded)) => {
+                assert_eq!(decoded.name(), dns.name());
+                assert_eq!(decoded, dns);
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn signable_bytes() {
+        let keys = vec![crypto::sign::gen_keypair(), crypto::sign::gen_keypair()];
+        let owners = keys.iter().map(|&(ref public_key, _)| public_key.clone()).collect::<Vec<_>>();
+        let identifier = NameType([5u8; 64]);
+        let mut structured_data = StructuredData::new(TYPE_TAG, identifier.clone(), 2, owners.clone(),
+                                                      b"data".to_vec()).unwrap();
+        structured_data.add_signature(&keys[1].0, &keys[1].1);
+
+        let mut expected = field(b"StructuredData");
+        expected.extend(number(TYPE_TAG));
+        expected.extend(field(&identifier.0));
+        expected.extend(number(2));
+        expected.extend(number(2));
+        expected.extend(field(&owners[0].0));
+        expected.extend(field(&owners[1].0));
+        expected.extend(field(b"data"));
+        expected.extend(number(MAX_STRUCTURED_DATA_SIZE as u64));
+        assert_eq!(structured_data.signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(&structured_data.signatures()[0].1, &expected, &owners[1]));
+
+        let fixed = StructuredData::new(TYPE_TAG, NameType([3u8; 64]), 2,
+                                        vec![crypto::sign::PublicKey([1u8; 32]), crypto::sign::PublicKey([2u8; 32])],
+                                        b"data".to_vec()).unwrap();
+        assert_eq!(fixed.signable_bytes().to_hex(),
+                   concat!("000000000000000e537472756374757265644461746100000000000003e800000000000000400303",
+                           "03030303030303030303030303030303030303030303030303030303030303030303030303030303",
+                           "03030303030303030303030303030303030303030303000000000000000200000000000000020000",
+                           "00000000002001010101010101010101010101010101010101010101010101010101010101010000",
+                           "00000000002002020202020202020202020202020202020202020202020202020202020202020000",
+                           "000000000004646174610000000000019000"));
+    }
 }
@@ -0,0 +1,109 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::collections::HashMap;
+
+/// Returns true if the tag falls within the range reserved for the types in `data_tags`
+pub fn is_reserved_tag(tag: u64) -> bool {
+    tag >= ::MAIDSAFE_TAG && tag < ::MAIDSAFE_TAG + ::MAIDSAFE_TAG_RANGE
+}
+
+/// TagRegistry
+///
+/// Records the application defined type tags used for StructuredData, refusing tags reserved for
+/// Maidsafe types and tags which are already registered.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::data::TagRegistry;
+///
+/// let mut registry = TagRegistry::new();
+/// assert!(registry.register(1000, "contact list".to_string()).is_ok());
+/// assert!(registry.register(1000, "dns record".to_string()).is_err());
+/// assert!(registry.register(maidsafe_types::data_tags::STRUCTURED_DATA_TAG, "dns record".to_string()).is_err());
+/// ```
+pub struct TagRegistry {
+    tags: HashMap<u64, String>,
+}
+
+impl TagRegistry {
+    /// Creates an empty registry
+    pub fn new() -> TagRegistry {
+        TagRegistry { tags: HashMap::new() }
+    }
+
+    /// Registers the tag with a description of the kind of data it identifies
+    pub fn register(&mut self, type_tag: u64, description: String) -> Result<(), ::Error> {
+        if is_reserved_tag(type_tag) {
+            return Err(::Error::ReservedTag(type_tag));
+        }
+        if self.tags.contains_key(&type_tag) {
+            return Err(::Error::TagAlreadyRegistered(type_tag));
+        }
+        let _ = self.tags.insert(type_tag, description);
+        Ok(())
+    }
+
+    /// Returns true if the tag has been registered
+    pub fn is_registered(&self, type_tag: u64) -> bool {
+        self.tags.contains_key(&type_tag)
+    }
+
+    /// Returns the description the tag was registered with
+    pub fn description(&self, type_tag: u64) -> Option<&String> {
+        self.tags.get(&type_tag)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reserved_tags() {
+        let mut registry = TagRegistry::new();
+        for tag in [::MAIDSAFE_TAG,
+                    ::data_tags::STRUCTURED_DATA_TAG,
+                    ::data_tags::SAFECOIN_TAG,
+                    ::MAIDSAFE_TAG + ::MAIDSAFE_TAG_RANGE - 1].iter() {
+            assert!(is_reserved_tag(*tag));
+            match registry.register(*tag, "reserved".to_string()) {
+                Err(::Error::ReservedTag(reserved)) => assert_eq!(reserved, *tag),
+                _ => panic!("Unexpected!"),
+            }
+            assert!(!registry.is_registered(*tag));
+        }
+        assert!(!is_reserved_tag(::MAIDSAFE_TAG - 1));
+        assert!(!is_reserved_tag(::MAIDSAFE_TAG + ::MAIDSAFE_TAG_RANGE));
+    }
+
+    #[test]
+    fn register() {
+        let mut registry = TagRegistry::new();
+        assert!(registry.register(1000, "contact list".to_string()).is_ok());
+        assert!(registry.register(1001, "dns record".to_string()).is_ok());
+        match registry.register(1000, "dns record".to_string()) {
+            Err(::Error::TagAlreadyRegistered(tag)) => assert_eq!(tag, 1000),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(registry.is_registered(1000));
+        assert_eq!(registry.description(1000), Some(&"contact list".to_string()));
+        assert_eq!(registry.description(1001), Some(&"dns record".to_string()));
+        assert!(registry.description(1002).is_none());
+    }
+}
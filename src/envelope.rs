@@ -0,0 +1,431 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Versioned wire format.
+//!
+//! An envelope is a CBOR item tagged `data_tags::ENVELOPE_TAG` holding a pair:
+//!
+//! ```text
+//! [format_version, payload]
+//! ```
+//!
+//! `payload` is the encoding of one of the types in `data_tags`, held as an array of bytes.  Data
+//! written before envelopes were introduced is a bare encoding, which is read as format version 0.
+//!
+//! ```text
+//! version 0  bare item, laid out as in version 1 except for:
+//!            StructuredData  [name, owner, value]; refused, it holds no owners' keys or signatures
+//!            SafeCoin        tagged LEGACY_SAFECOIN_TAG, [name, owners, previous_owners,
+//!                            signatures]; refused, its owners are names and it has no value
+//!            PublicIdType    no sequence number; read as sequence number 0
+//! version 1  envelope around an item laid out as by the type's Encodable implementation
+//! ```
+//!
+//! The types added since version 0 was deployed have only ever had their version 1 layout.  A
+//! version 0 public id keeps its name, but what it holds in place of a signature is part of the
+//! signed message, so it fails `verify()` and has to be reissued.
+//!
+//! `AnyData::from_bytes` accepts both forms, so readers can be updated before any writer starts
+//! sending envelopes.  When the layout of a type changes, `FORMAT_VERSION` is incremented and
+//! `upgrade_payload` learns to convert payloads written in the earlier versions, so data already
+//! stored keeps decoding.  A payload written in a later version than this crate knows is refused
+//! with `Error::UnsupportedFormatVersion` rather than misread, and an item which cannot be
+//! converted with `Error::UnconvertibleLegacyData`.  Decoding `AnyData` from a stream of items
+//! reads version 0 public ids, but neither converts nor refuses the other version 0 layouts.
+
+use any_data::{AnyData, is_known_tag, peek_tag};
+use cbor;
+use cbor::CborTagEncode;
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+
+/// Format version written by this crate
+pub const FORMAT_VERSION: u64 = 1;
+
+/// Format version of data written without an envelope
+pub const LEGACY_FORMAT_VERSION: u64 = 0;
+
+/// Tag of SafeCoin in format version 0, before it was moved among the data tags
+pub const LEGACY_SAFECOIN_TAG: u64 = ::MAIDSAFE_TAG + 3;
+
+struct Envelope {
+    version: u64,
+    payload: Vec<u8>,
+}
+
+impl Encodable for Envelope {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        CborTagEncode::new(::data_tags::ENVELOPE_TAG, &(self.version, &self.payload)).encode(e)
+    }
+}
+
+// Unlike the tagged types, which leave their tag to AnyData, the envelope reads its own tag
+impl Decodable for Envelope {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Envelope, D::Error> {
+        let _ = try!(d.read_u64());
+        let (version, payload) = try!(Decodable::decode(d));
+        Ok(Envelope { version: version, payload: payload })
+    }
+}
+
+/// Encodes item in an envelope of the current format version
+pub fn wrap<T: Encodable>(item: &T) -> Result<Vec<u8>, ::Error> {
+    let mut e = cbor::Encoder::from_memory();
+    try!(e.encode(&[item]));
+    wrap_bytes(e.as_bytes())
+}
+
+/// Puts an item which has already been encoded, such as the output of
+/// `IdType::serialise_with_secret_keys`, in an envelope of the current format version
+pub fn wrap_bytes(payload: &[u8]) -> Result<Vec<u8>, ::Error> {
+    let tag = try!(peek_tag(payload));
+    if !is_known_tag(tag) {
+        return Err(::Error::UnknownTag(tag));
+    }
+    let mut e = cbor::Encoder::from_memory();
+    try!(e.encode(&[Envelope { version: FORMAT_VERSION, payload: payload.to_vec() }]));
+    Ok(e.into_bytes())
+}
+
+/// Returns the format version bytes were written in, and the item they hold laid out as in the
+/// current version.  Bytes without an envelope are read as version 0.
+pub fn open(bytes: &[u8]) -> Result<(u64, Vec<u8>), ::Error> {
+    if try!(peek_tag(bytes)) != ::data_tags::ENVELOPE_TAG {
+        return Ok((LEGACY_FORMAT_VERSION, try!(upgrade_payload(LEGACY_FORMAT_VERSION, bytes.to_vec()))));
+    }
+    let mut d = cbor::Decoder::from_bytes(bytes);
+    let envelope: Envelope = try!(try!(d.decode().next().ok_or(cbor::CborError::UnexpectedEOF)));
+    let payload = try!(upgrade_payload(envelope.version, envelope.payload));
+    Ok((envelope.version, payload))
+}
+
+// Number of fields in the version 0 layouts of StructuredData and PublicIdType
+const LEGACY_STRUCTURED_DATA_FIELDS: usize = 3;
+const LEGACY_PUBLIC_ID_FIELDS: usize = 5;
+
+/// Reads the tag and number of fields of an encoded item
+struct LayoutPeek(u64, usize);
+
+impl Decodable for LayoutPeek {
+    fn decode<D: Decoder>(d: &mut D) -> Result<LayoutPeek, D::Error> {
+        let tag = try!(d.read_u64());
+        let len = try!(d.read_seq(|_, len| Ok(len)));
+        Ok(LayoutPeek(tag, len))
+    }
+}
+
+fn peek_layout(bytes: &[u8]) -> Result<(u64, usize), ::Error> {
+    let mut d = cbor::Decoder::from_bytes(bytes);
+    let LayoutPeek(tag, len) = try!(try!(d.decode().next().ok_or(cbor::CborError::UnexpectedEOF)));
+    Ok((tag, len))
+}
+
+// Converts an item written in format version 0 to the current layout, see the module doc
+fn upgrade_legacy(payload: Vec<u8>) -> Result<Vec<u8>, ::Error> {
+    let (tag, len) = match try!(peek_tag(&payload)) {
+        LEGACY_SAFECOIN_TAG => return Err(::Error::UnconvertibleLegacyData(LEGACY_SAFECOIN_TAG)),
+        ::data_tags::STRUCTURED_DATA_TAG |
+        ::data_tags::PUBLIC_MAID_TAG |
+        ::data_tags::PUBLIC_MPID_TAG => try!(peek_layout(&payload)),
+        _ => return Ok(payload),
+    };
+    if tag == ::data_tags::STRUCTURED_DATA_TAG && len == LEGACY_STRUCTURED_DATA_FIELDS {
+        return Err(::Error::UnconvertibleLegacyData(tag));
+    }
+    if tag == ::data_tags::STRUCTURED_DATA_TAG || len != LEGACY_PUBLIC_ID_FIELDS {
+        return Ok(payload);
+    }
+    let mut d = cbor::Decoder::from_bytes(&payload[..]);
+    let data: AnyData = try!(try!(d.decode().next().ok_or(cbor::CborError::UnexpectedEOF)));
+    let mut e = cbor::Encoder::from_memory();
+    match data {
+        AnyData::PublicMaid(ref item) | AnyData::PublicMpid(ref item) => try!(e.encode(&[item])),
+        _ => return Err(::Error::CorruptedData),
+    }
+    Ok(e.into_bytes())
+}
+
+/// Converts the payload of an envelope written in format version to the current layout
+pub fn upgrade_payload(version: u64, payload: Vec<u8>) -> Result<Vec<u8>, ::Error> {
+    let payload = match version {
+        LEGACY_FORMAT_VERSION => try!(upgrade_legacy(payload)),
+        FORMAT_VERSION => payload,
+        _ => return Err(::Error::UnsupportedFormatVersion(version)),
+    };
+    // Envelopes are never nested
+    if try!(peek_tag(&payload)) == ::data_tags::ENVELOPE_TAG {
+        return Err(::Error::CorruptedData);
+    }
+    Ok(payload)
+}
+
+/// Rewrites bytes of any supported format version, with or without an envelope, in the current one
+pub fn upgrade(bytes: &[u8]) -> Result<Vec<u8>, ::Error> {
+    let (_, payload) = try!(open(bytes));
+    wrap_bytes(&payload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use any_data::AnyData;
+    use cbor;
+    use cbor::CborTagEncode;
+    use data::ImmutableData;
+    use id::PublicIdType;
+    use routing::NameType;
+    use routing::sendable::Sendable;
+    use rustc_serialize::hex::{FromHex, ToHex};
+    use Random;
+
+    // Golden vectors of every type.  Version 0 vectors are bare items as deployed before envelopes
+    // were introduced, version 1 vectors the payloads of current envelopes, which for the types
+    // whose layout has not changed are the version 0 vectors themselves.  The keys and signatures
+    // are fixed bytes, which decoding does not check.  NameType is encoded by routing, so "{name}"
+    // stands for the encoding of NameType([3u8; 64]), and envelopes around payloads holding a name
+    // are built by envelope_v1, which ENVELOPE_V1 pins down.
+    const ENVELOPE_V1: &'static str = concat!(
+        "da0053aa6d82019218da00185318aa185d1886181818671818186f1818186c18181864181818651818186e");
+    const IMMUTABLE_DATA_V0: &'static str = "da0053aa5d861867186f186c18641865186e";
+    const IMMUTABLE_DATA_BACKUP_V0: &'static str = "da0053aa5e861867186f186c18641865186e";
+    const IMMUTABLE_DATA_SACRIFICIAL_V0: &'static str = "da0053aa5f861867186f186c18641865186e";
+    const AN_MAID_V0: &'static str = concat!(
+        "da0053aa62858718351834183818331831183018368718351834183818331831183018378718351834183818",
+        "3318311830183998200707070707070707070707070707070707070707070707070707070707070707984005",
+        "0505050505050505050505050505050505050505050505050505050505050505050505050505050505050505",
+        "05050505050505050505050505050505050505");
+    const MAID_V0: &'static str = concat!(
+        "da0053aa63858718351834183818331831183018379820010101010101010101010101010101010101010101",
+        "0101010101010101010101982002020202020202020202020202020202020202020202020202020202020202",
+        "0298400505050505050505050505050505050505050505050505050505050505050505050505050505050505",
+        "0505050505050505050505050505050505050505050505982006060606060606060606060606060606060606",
+        "06060606060606060606060606");
+    const PUBLIC_MAID_V0: &'static str = concat!(
+        "da0053aa65858718351834183818331831183018399820010101010101010101010101010101010101010101",
+        "0101010101010101010101982002020202020202020202020202020202020202020202020202020202020202",
+        "0298200707070707070707070707070707070707070707070707070707070707070707984008080808080808",
+        "0808080808080808080808080808080808080808080808080808080808080808080808080808080808080808",
+        "08080808080808080808080808");
+    const PUBLIC_MAID_V1: &'static str = concat!(
+        "da0053aa65868718351834183818331831183018399820010101010101010101010101010101010101010101",
+        "0101010101010101010101982002020202020202020202020202020202020202020202020202020202020202",
+        "0298200707070707070707070707070707070707070707070707070707070707070707009840080808080808",
+        "0808080808080808080808080808080808080808080808080808080808080808080808080808080808080808",
+        "0808080808080808080808080808");
+    const ID_PUBLIC_PART_V0: &'static str = concat!(
+        "da0053aa6b838718351834183818331831183018379820010101010101010101010101010101010101010101",
+        "0101010101010101010101982002020202020202020202020202020202020202020202020202020202020202",
+        "02");
+    const REVOCATION_ID_PUBLIC_PART_V0: &'static str = concat!(
+        "da0053aa6e848718351834183818331831183018368718351834183818331831183018378718351834183818",
+        "3318311830183998200707070707070707070707070707070707070707070707070707070707070707");
+    const DATA_MAP_V0: &'static str = "da0053aa688280861867186f186c18641865186e";
+    const STRUCTURED_DATA_V0: &'static str = "da0053aa6083{name}{name}81{name}";
+    const STRUCTURED_DATA_V1: &'static str = concat!(
+        "da0053aa60881903e8{name}0281982001010101010101010101010101010101010101010101010101010101",
+        "01010101861867186f186c18641865186e1a0001900081982001010101010101010101010101010101010101",
+        "0101010101010101010101010181984008080808080808080808080808080808080808080808080808080808",
+        "080808080808080808080808080808080808080808080808080808080808080808080808");
+    const SAFECOIN_V0: &'static str = "da0053a9fb84{name}81{name}81{name}80";
+    const SAFECOIN_V1: &'static str = concat!(
+        "da0053aa6788{name}011a3b9aca008198200101010101010101010101010101010101010101010101010101",
+        "0101010101018198200707070707070707070707070707070707070707070707070707070707070707819820",
+        "0707070707070707070707070707070707070707070707070707070707070707819840080808080808080808",
+        "0808080808080808080808080808080808080808080808080808080808080808080808080808080808080808",
+        "080808080808080808080880");
+    const SUPERSEDED_RECORD_V0: &'static str = concat!(
+        "da0053aa6985{name}{name}0198200707070707070707070707070707070707070707070707070707070707",
+        "0707079840080808080808080808080808080808080808080808080808080808080808080808080808080808",
+        "08080808080808080808080808080808080808080808080808");
+    const REVOCATION_CERTIFICATE_V0: &'static str = concat!(
+        "da0053aa6a85{name}011a5611b1009820070707070707070707070707070707070707070707070707070707",
+        "0707070707984008080808080808080808080808080808080808080808080808080808080808080808080808",
+        "080808080808080808080808080808080808080808080808080808");
+    const WALLET_V0: &'static str = concat!(
+        "da0053aa6c83{name}9820010101010101010101010101010101010101010101010101010101010101010180");
+
+    fn golden(vector: &str) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&NameType([3u8; 64])]).unwrap();
+        vector.replace("{name}", &e.as_bytes().to_hex()).from_hex().unwrap()
+    }
+
+    fn encode(data: &AnyData) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        match *data {
+            AnyData::StructuredData(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::ImmutableData(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::ImmutableDataBackup(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::ImmutableDataSacrificial(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::Maid(ref item) | AnyData::Mpid(ref item) => return item.serialise_with_secret_keys(),
+            AnyData::AnMaid(ref item) | AnyData::AnMpid(ref item) => return item.serialise_with_secret_key(),
+            AnyData::PublicMaid(ref item) | AnyData::PublicMpid(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::SafeCoin(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::DataMap(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::SupersededRecord(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::RevocationCertificate(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::IdTypePublicPart(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::Wallet(ref item) => e.encode(&[item]).unwrap(),
+            AnyData::RevocationIdTypePublicPart(ref item) => e.encode(&[item]).unwrap(),
+        }
+        e.into_bytes()
+    }
+
+    // Encodes payload as an array of bytes in a version 1 envelope
+    fn envelope_v1(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = "da0053aa6d8201".from_hex().unwrap();
+        match payload.len() {
+            len if len < 24 => bytes.push(0x80 + len as u8),
+            len if len < 256 => bytes.extend([0x98, len as u8].iter().cloned()),
+            len => bytes.extend([0x99, (len >> 8) as u8, len as u8].iter().cloned()),
+        }
+        for &byte in payload {
+            if byte >= 24 {
+                bytes.push(0x18);
+            }
+            bytes.push(byte);
+        }
+        bytes
+    }
+
+    fn check_current(tag: u64, payload: &str) {
+        let payload = golden(payload);
+        let envelope = envelope_v1(&payload);
+        let decoded = AnyData::from_bytes(&envelope).unwrap();
+        assert_eq!(decoded.tag(), tag);
+        assert_eq!(encode(&decoded), payload);
+        assert_eq!(open(&envelope).unwrap(), (FORMAT_VERSION, payload.clone()));
+        assert_eq!(upgrade(&envelope).unwrap(), envelope);
+        assert_eq!(open(&payload).unwrap(), (LEGACY_FORMAT_VERSION, payload.clone()));
+        assert_eq!(upgrade(&payload).unwrap(), envelope);
+    }
+
+    fn check_refused(tag: u64, v0: &str) {
+        let v0 = golden(v0);
+        match AnyData::from_bytes(&v0) {
+            Err(::Error::UnconvertibleLegacyData(refused)) => assert_eq!(refused, tag),
+            _ => panic!("Unexpected!"),
+        }
+        match upgrade(&v0) {
+            Err(::Error::UnconvertibleLegacyData(refused)) => assert_eq!(refused, tag),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn golden_vectors() {
+        use data_tags::*;
+        assert_eq!(envelope_v1(&golden(IMMUTABLE_DATA_V0)), golden(ENVELOPE_V1));
+
+        // Unchanged since version 0
+        check_current(IMMUTABLE_DATA_TAG, IMMUTABLE_DATA_V0);
+        check_current(IMMUTABLE_DATA_BACKUP_TAG, IMMUTABLE_DATA_BACKUP_V0);
+        check_current(IMMUTABLE_DATA_SACRIFICIAL_TAG, IMMUTABLE_DATA_SACRIFICIAL_V0);
+        check_current(AN_MAID_TAG, AN_MAID_V0);
+        check_current(MAID_TAG, MAID_V0);
+
+        // Added since version 0
+        check_current(ID_PUBLIC_PART_TAG, ID_PUBLIC_PART_V0);
+        check_current(REVOCATION_ID_PUBLIC_PART_TAG, REVOCATION_ID_PUBLIC_PART_V0);
+        check_current(DATA_MAP_TAG, DATA_MAP_V0);
+        check_current(SUPERSEDED_ID_TAG, SUPERSEDED_RECORD_V0);
+        check_current(REVOCATION_CERTIFICATE_TAG, REVOCATION_CERTIFICATE_V0);
+        check_current(WALLET_TAG, WALLET_V0);
+
+        // Changed since version 0, whose layout is refused
+        check_current(STRUCTURED_DATA_TAG, STRUCTURED_DATA_V1);
+        check_refused(STRUCTURED_DATA_TAG, STRUCTURED_DATA_V0);
+        check_current(SAFECOIN_TAG, SAFECOIN_V1);
+        check_refused(LEGACY_SAFECOIN_TAG, SAFECOIN_V0);
+        assert_eq!(LEGACY_SAFECOIN_TAG, 5483_003);
+    }
+
+    #[test]
+    fn legacy_public_id() {
+        use data_tags::PUBLIC_MAID_TAG;
+        check_current(PUBLIC_MAID_TAG, PUBLIC_MAID_V1);
+        let v0 = golden(PUBLIC_MAID_V0);
+        let v1 = golden(PUBLIC_MAID_V1);
+        assert_eq!(open(&v0).unwrap(), (LEGACY_FORMAT_VERSION, v1.clone()));
+        assert_eq!(upgrade(&v0).unwrap(), envelope_v1(&v1));
+
+        let current = match AnyData::from_bytes(&v1).unwrap() {
+            AnyData::PublicMaid(public_maid) => public_maid,
+            _ => panic!("Unexpected!"),
+        };
+        match AnyData::from_bytes(&v0).unwrap() {
+            AnyData::PublicMaid(public_maid) => {
+                assert_eq!(public_maid.sequence_number(), 0);
+                assert_eq!(public_maid.public_keys().0 .0, [1u8; 32]);
+                assert_eq!(public_maid.revocation_public_key().0, [7u8; 32]);
+                assert_eq!(public_maid.name(), current.name());
+                assert!(!public_maid.verify());
+            },
+            _ => panic!("Unexpected!"),
+        }
+        match PublicIdType::decode_and_verify(&v0) {
+            Err(::Error::SignatureVerification) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn wrap_and_open() {
+        let immutable_data = ImmutableData::generate_random();
+        let wrapped = wrap(&immutable_data).unwrap();
+        let (version, payload) = open(&wrapped).unwrap();
+        assert_eq!(version, FORMAT_VERSION);
+        assert_eq!(payload, immutable_data.serialised_contents());
+        match AnyData::from_bytes(&wrapped) {
+            Ok(AnyData::ImmutableData(decoded)) => assert_eq!(decoded, immutable_data),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Envelopes also decode from a stream of items
+        let mut d = cbor::Decoder::from_bytes(&wrapped[..]);
+        match d.decode::<AnyData>().next().unwrap() {
+            Ok(AnyData::ImmutableData(decoded)) => assert_eq!(decoded, immutable_data),
+            _ => panic!("Unexpected!"),
+        }
+
+        assert!(wrap_bytes(&[1u8, 2, 3]).is_err());
+        match wrap_bytes(&wrapped) {
+            Err(::Error::UnknownTag(tag)) => assert_eq!(tag, ::data_tags::ENVELOPE_TAG),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let payload = ImmutableData::generate_random().serialised_contents();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(::data_tags::ENVELOPE_TAG, &(FORMAT_VERSION + 1, &payload))]).unwrap();
+        match AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::UnsupportedFormatVersion(version)) => assert_eq!(version, FORMAT_VERSION + 1),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(upgrade(e.as_bytes()).is_err());
+
+        // Nested envelopes are refused
+        let nested = wrap_bytes(&payload).unwrap();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(::data_tags::ENVELOPE_TAG, &(FORMAT_VERSION, &nested))]).unwrap();
+        match open(e.as_bytes()) {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
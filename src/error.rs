@@ -0,0 +1,350 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor;
+use routing::NameType;
+use std::error;
+use std::io;
+use std::fmt;
+
+/// Errors returned by the maidsafe_types library
+#[derive(Debug)]
+pub enum Error {
+    /// A key or signature did not have the expected number of bytes
+    InvalidKeyLength {
+        /// Number of bytes required
+        expected: usize,
+        /// Number of bytes received
+        actual: usize,
+    },
+    /// A type tag could not be parsed or did not match the expected type
+    BadTypeTag,
+    /// A signature did not verify against the expected public key
+    SignatureVerification,
+    /// Fewer than the required number of owners have validly signed
+    InsufficientSignatures {
+        /// Number of valid signatures required
+        required: usize,
+        /// Number of valid signatures present
+        actual: usize,
+    },
+    /// Data could not be decrypted or failed authentication
+    Decryption,
+    /// The cbor tag does not belong to any of the types in `data_tags`
+    UnknownTag(u64),
+    /// The tag falls within the range reserved for the types in `data_tags`
+    ReservedTag(u64),
+    /// The tag has already been registered
+    TagAlreadyRegistered(u64),
+    /// A chunk listed in a DataMap could not be retrieved
+    ChunkNotFound(NameType),
+    /// An update did not carry the version following the current one
+    InvalidVersion {
+        /// Version required
+        expected: u64,
+        /// Version received
+        actual: u64,
+    },
+    /// An update did not refer to the same name as the current data
+    NameMismatch,
+    /// The same key was listed more than once among the owners
+    DuplicateOwner,
+    /// Data exceeded the maximum permitted size
+    DataTooLarge {
+        /// Maximum size in bytes
+        max: usize,
+        /// Size in bytes received
+        actual: usize,
+    },
+    /// A revocation reason code was not recognised
+    InvalidReasonCode(u64),
+    /// A chain of rotations does not hold one record between each pair of successive public ids
+    MalformedChain,
+    /// Keys could not be derived from the supplied credentials
+    KeyDerivation,
+    /// An encrypted keystore could not be opened with the supplied password
+    WrongPassword,
+    /// An encrypted keystore has been truncated or altered
+    CorruptedData,
+    /// No coin with the given name has been minted
+    UnknownCoin(NameType),
+    /// A coin with the given name has already been minted
+    CoinAlreadyMinted(NameType),
+    /// A coin offered as newly minted has previous owners or was split off another coin
+    NotNewlyMinted,
+    /// A transfer was not made by the current owners of the coin
+    NotOwner,
+    /// A coin has already been transferred elsewhere
+    DoubleSpend(NameType),
+    /// A farming proof was not made from the chunk it names
+    InvalidFarmingProof,
+    /// A farming attempt fell outside the farming rate
+    FarmingAttemptFailed,
+    /// A farming proof does not answer a challenge the coin space has outstanding
+    UnknownFarmingChallenge,
+    /// A payment exceeds the balance available
+    InsufficientFunds {
+        /// Amount required
+        required: ::coin::Amount,
+        /// Amount available
+        available: ::coin::Amount,
+    },
+    /// An amount of SafeCoin overflowed or went below zero
+    AmountOutOfRange,
+    /// The outputs of a split do not add up to the value of the coin split
+    ValueNotConserved,
+    /// Data was written in a format version this crate does not know
+    UnsupportedFormatVersion(u64),
+    /// An item written in format version 0, with the given tag, whose layout cannot be converted
+    /// to the current one
+    UnconvertibleLegacyData(u64),
+    /// A JSON representation could not be read
+    InvalidJson(String),
+    /// An encrypted stream ended before its final frame
+    TruncatedStream,
+    /// Reading or writing a stream failed
+    Io(io::Error),
+    /// The underlying cbor encoding or decoding failed
+    Cbor(cbor::CborError),
+}
+
+// Start of the description a Decodable impl gives its decoder for a typed error, which is followed
+// by the error's Debug representation
+const DECODE_ERROR_PREFIX: &'static str = "maidsafe_types::Error::";
+
+impl Error {
+    /// Returns the description to give a decoder's `error` when a `Decodable` impl fails with self.
+    /// Converting the resulting cbor error recovers self, unless self holds a name, an amount, a
+    /// string or another error, in which case the cbor error carrying the description is kept.
+    pub fn decode_description(&self) -> String {
+        format!("{}{:?}", DECODE_ERROR_PREFIX, self)
+    }
+
+    fn from_decode_description(description: &str) -> Option<Error> {
+        if !description.starts_with(DECODE_ERROR_PREFIX) {
+            return None;
+        }
+        let debug = &description[DECODE_ERROR_PREFIX.len()..];
+        let variant = debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or("");
+        let mut numbers = Vec::new();
+        for number in debug[variant.len()..].split(|c: char| !c.is_digit(10)).filter(|number| !number.is_empty()) {
+            match number.parse::<u64>() {
+                Ok(number) => numbers.push(number),
+                Err(_) => return None,
+            }
+        }
+        let error = match (variant, numbers.len()) {
+            ("InvalidKeyLength", 2) =>
+                Error::InvalidKeyLength { expected: numbers[0] as usize, actual: numbers[1] as usize },
+            ("BadTypeTag", 0) => Error::BadTypeTag,
+            ("SignatureVerification", 0) => Error::SignatureVerification,
+            ("InsufficientSignatures", 2) =>
+                Error::InsufficientSignatures { required: numbers[0] as usize, actual: numbers[1] as usize },
+            ("Decryption", 0) => Error::Decryption,
+            ("UnknownTag", 1) => Error::UnknownTag(numbers[0]),
+            ("ReservedTag", 1) => Error::ReservedTag(numbers[0]),
+            ("TagAlreadyRegistered", 1) => Error::TagAlreadyRegistered(numbers[0]),
+            ("InvalidVersion", 2) => Error::InvalidVersion { expected: numbers[0], actual: numbers[1] },
+            ("NameMismatch", 0) => Error::NameMismatch,
+            ("DuplicateOwner", 0) => Error::DuplicateOwner,
+            ("DataTooLarge", 2) => Error::DataTooLarge { max: numbers[0] as usize, actual: numbers[1] as usize },
+            ("InvalidReasonCode", 1) => Error::InvalidReasonCode(numbers[0]),
+            ("MalformedChain", 0) => Error::MalformedChain,
+            ("KeyDerivation", 0) => Error::KeyDerivation,
+            ("WrongPassword", 0) => Error::WrongPassword,
+            ("CorruptedData", 0) => Error::CorruptedData,
+            ("NotNewlyMinted", 0) => Error::NotNewlyMinted,
+            ("NotOwner", 0) => Error::NotOwner,
+            ("AmountOutOfRange", 0) => Error::AmountOutOfRange,
+            ("ValueNotConserved", 0) => Error::ValueNotConserved,
+            ("InvalidFarmingProof", 0) => Error::InvalidFarmingProof,
+            ("FarmingAttemptFailed", 0) => Error::FarmingAttemptFailed,
+            ("UnknownFarmingChallenge", 0) => Error::UnknownFarmingChallenge,
+            ("UnsupportedFormatVersion", 1) => Error::UnsupportedFormatVersion(numbers[0]),
+            ("UnconvertibleLegacyData", 1) => Error::UnconvertibleLegacyData(numbers[0]),
+            ("TruncatedStream", 0) => Error::TruncatedStream,
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
+/// A decode which failed inside one of this crate's `Decodable` impls gives the typed error raised
+/// there, read back from its description, rather than the cbor error carrying that description
+impl From<cbor::CborError> for Error {
+    fn from(error: cbor::CborError) -> Error {
+        if let cbor::CborError::AtOffset { kind: cbor::ReadError::Other(ref description), .. } = error {
+            if let Some(typed) = Error::from_decode_description(description) {
+                return typed;
+            }
+        }
+        Error::Cbor(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::InvalidKeyLength { .. } => "Invalid key length",
+            Error::BadTypeTag => "Bad type tag",
+            Error::SignatureVerification => "Signature verification failed",
+            Error::InsufficientSignatures { .. } => "Insufficient signatures",
+            Error::Decryption => "Decryption failed",
+            Error::UnknownTag(_) => "Unknown tag",
+            Error::ReservedTag(_) => "Reserved tag",
+            Error::TagAlreadyRegistered(_) => "Tag already registered",
+            Error::ChunkNotFound(_) => "Chunk not found",
+            Error::InvalidVersion { .. } => "Invalid version",
+            Error::NameMismatch => "Name mismatch",
+            Error::DuplicateOwner => "Duplicate owner",
+            Error::DataTooLarge { .. } => "Data too large",
+            Error::InvalidReasonCode(_) => "Invalid reason code",
+            Error::MalformedChain => "Malformed chain",
+            Error::KeyDerivation => "Key derivation failed",
+            Error::WrongPassword => "Wrong password",
+            Error::CorruptedData => "Corrupted data",
+            Error::UnknownCoin(_) => "Unknown coin",
+            Error::CoinAlreadyMinted(_) => "Coin already minted",
+            Error::NotNewlyMinted => "Not newly minted",
+            Error::NotOwner => "Not owner",
+            Error::DoubleSpend(_) => "Double spend",
+            Error::InsufficientFunds { .. } => "Insufficient funds",
+            Error::AmountOutOfRange => "Amount out of range",
+            Error::ValueNotConserved => "Value not conserved",
+            Error::InvalidFarmingProof => "Invalid farming proof",
+            Error::FarmingAttemptFailed => "Farming attempt failed",
+            Error::UnknownFarmingChallenge => "Unknown farming challenge",
+            Error::UnsupportedFormatVersion(_) => "Unsupported format version",
+            Error::UnconvertibleLegacyData(_) => "Unconvertible legacy data",
+            Error::InvalidJson(_) => "Invalid json",
+            Error::TruncatedStream => "Truncated stream",
+            Error::Io(_) => "Io error",
+            Error::Cbor(_) => "Cbor error",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidKeyLength { expected, actual } =>
+                write!(f, "Invalid key length: expected {} bytes, got {}", expected, actual),
+            Error::InsufficientSignatures { required, actual } =>
+                write!(f, "Insufficient signatures: {} valid of {} required", actual, required),
+            Error::DataTooLarge { max, actual } =>
+                write!(f, "Data too large: {} bytes exceeds the maximum of {}", actual, max),
+            Error::UnknownTag(tag) => write!(f, "Unknown tag {}", tag),
+            Error::ReservedTag(tag) => write!(f, "Reserved tag {}", tag),
+            Error::TagAlreadyRegistered(tag) => write!(f, "Tag {} already registered", tag),
+            Error::ChunkNotFound(ref name) => write!(f, "Chunk not found: {:?}", name),
+            Error::InvalidVersion { expected, actual } =>
+                write!(f, "Invalid version: expected {}, got {}", expected, actual),
+            Error::InvalidReasonCode(code) => write!(f, "Invalid reason code {}", code),
+            Error::UnknownCoin(ref name) => write!(f, "Unknown coin: {:?}", name),
+            Error::CoinAlreadyMinted(ref name) => write!(f, "Coin already minted: {:?}", name),
+            Error::DoubleSpend(ref name) => write!(f, "Double spend of coin: {:?}", name),
+            Error::InsufficientFunds { required, available } =>
+                write!(f, "Insufficient funds: {} required, {} available", required, available),
+            Error::UnsupportedFormatVersion(version) => write!(f, "Unsupported format version {}", version),
+            Error::UnconvertibleLegacyData(tag) =>
+                write!(f, "Legacy data with tag {} cannot be converted to the current layout", tag),
+            Error::InvalidJson(ref reason) => write!(f, "Invalid json: {}", reason),
+            Error::Io(ref error) => write!(f, "Io error: {}", error),
+            Error::Cbor(ref error) => write!(f, "Cbor error: {:?}", error),
+            _ => write!(f, "{}", error::Error::description(self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cbor;
+
+    #[test]
+    fn display() {
+        let error = Error::InvalidKeyLength { expected: 32, actual: 31 };
+        assert_eq!(error.to_string(), "Invalid key length: expected 32 bytes, got 31".to_string());
+        assert_eq!(Error::UnknownTag(7).to_string(), "Unknown tag 7".to_string());
+        assert_eq!(Error::Decryption.to_string(), "Decryption failed".to_string());
+    }
+
+    fn decode_failure(error: &Error) -> cbor::CborError {
+        cbor::CborError::AtOffset { kind: cbor::ReadError::Other(error.decode_description()), offset: 0 }
+    }
+
+    #[test]
+    fn from_cbor_error() {
+        match Error::from(cbor::CborError::UnexpectedEOF) {
+            Error::Cbor(cbor::CborError::UnexpectedEOF) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let recovered = vec![Error::InvalidKeyLength { expected: 32, actual: 31 }, Error::BadTypeTag,
+                             Error::InsufficientSignatures { required: 2, actual: 1 }, Error::UnknownTag(7),
+                             Error::InvalidVersion { expected: 3, actual: ::std::u64::MAX },
+                             Error::DataTooLarge { max: 10, actual: 11 }, Error::InvalidReasonCode(9),
+                             Error::CorruptedData, Error::NotOwner, Error::UnsupportedFormatVersion(2),
+                             Error::UnconvertibleLegacyData(5483_003)];
+        for error in recovered {
+            assert_eq!(format!("{:?}", Error::from(decode_failure(&error))), format!("{:?}", error));
+        }
+
+        // Errors holding more than numbers keep the cbor error and its description
+        let name = ::routing::NameType([3u8; 64]);
+        match Error::from(decode_failure(&Error::DoubleSpend(name))) {
+            Error::Cbor(cbor::CborError::AtOffset { kind: cbor::ReadError::Other(description), .. }) =>
+                assert!(description.starts_with("maidsafe_types::Error::DoubleSpend")),
+            _ => panic!("Unexpected!"),
+        }
+        match Error::from(cbor::CborError::AtOffset { kind: cbor::ReadError::Other("Bad".to_string()), offset: 0 }) {
+            Error::Cbor(_) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn swallowed_decode_failure() {
+        // A decode whose failure is dropped leaves nothing behind to be reported by the next one
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&cbor::CborTagEncode::new(::data_tags::REVOCATION_CERTIFICATE_TAG,
+                                             &(&::routing::NameType([3u8; 64]), 9u64, 0u64, vec![2u8], vec![3u8]))])
+         .unwrap();
+        let mut d = cbor::Decoder::from_bytes(e.as_bytes());
+        assert!(d.decode::<::AnyData>().next().unwrap().is_err());
+        match Error::from(cbor::CborError::UnexpectedEOF) {
+            Error::Cbor(cbor::CborError::UnexpectedEOF) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match ::AnyData::from_bytes(&[0xf6]) {
+            Err(Error::Cbor(_)) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // and the typed error of a failure is still given where it is converted
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(Error::InvalidReasonCode(code)) => assert_eq!(code, 9),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
@@ -25,6 +25,13 @@ pub fn slice_equal<T: PartialEq>(lhs: &[T], rhs: &[T]) -> bool {
     lhs.len() == rhs.len() && lhs.iter().zip(rhs.iter()).all(|(a, b)| a == b)
 }
 
+///
+/// Returns true if both lists hold the same signing public keys in the same order
+///
+pub fn keys_equal(lhs: &[crypto::sign::PublicKey], rhs: &[crypto::sign::PublicKey]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs.iter()).all(|(l, r)| slice_equal(&l.0, &r.0))
+}
+
 ///
 /// Convert a container to an array. If the container is not the exact size specified, None is
 /// returned. Otherwise, all of the elements are moved into the array.
@@ -52,6 +59,65 @@ macro_rules! convert_to_array {
     }};
 }
 
+///
+/// Evaluates a `Result<T, ::Error>` inside a `Decodable` impl, converting any error into the
+/// decoder's error type.  The decoder's error carries the typed error's decode description, from
+/// which it is recovered when converted into an `::Error`.
+///
+macro_rules! try_decode {
+    ($decoder:ident, $result:expr) => {{
+        match $result {
+            Ok(value) => value,
+            Err(error) => return Err($decoder.error(&::Error::decode_description(&error))),
+        }
+    }};
+}
+
+///
+/// Returns an InvalidKeyLength error if the actual length differs from the expected one
+///
+pub fn check_length(actual: usize, expected: usize) -> Result<(), ::Error> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(::Error::InvalidKeyLength { expected: expected, actual: actual })
+    }
+}
+
+///
+/// Parses a type tag serialised as its decimal string representation
+///
+pub fn parse_type_tag(tag_vec: Vec<u8>) -> Result<u64, ::Error> {
+    match String::from_utf8(tag_vec) {
+        Ok(string) => string.parse::<u64>().map_err(|_| ::Error::BadTypeTag),
+        Err(_) => Err(::Error::BadTypeTag),
+    }
+}
+
+///
+/// Converts serialised signing public keys, checking the length of each
+///
+pub fn vecs_to_public_keys(key_vecs: Vec<Vec<u8>>) -> Result<Vec<crypto::sign::PublicKey>, ::Error> {
+    let mut public_keys = Vec::with_capacity(key_vecs.len());
+    for key_vec in key_vecs {
+        try!(check_length(key_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        public_keys.push(crypto::sign::PublicKey(convert_to_array!(key_vec, crypto::sign::PUBLICKEYBYTES).unwrap()));
+    }
+    Ok(public_keys)
+}
+
+///
+/// Converts serialised signatures, checking the length of each
+///
+pub fn vecs_to_signatures(signature_vecs: Vec<Vec<u8>>) -> Result<Vec<crypto::sign::Signature>, ::Error> {
+    let mut signatures = Vec::with_capacity(signature_vecs.len());
+    for signature_vec in signature_vecs {
+        try!(check_length(signature_vec.len(), crypto::sign::SIGNATUREBYTES));
+        signatures.push(crypto::sign::Signature(convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES).unwrap()));
+    }
+    Ok(signatures)
+}
+
 ///
 /// Return NameType using Public signing & encryption keys
 ///
@@ -101,6 +167,14 @@ mod test {
         assert!(!slice_equal(&data2, &data1));
     }
 
+    #[test]
+    fn compare_keys() {
+        let keys = vec![crypto::sign::PublicKey([1u8; 32]), crypto::sign::PublicKey([2u8; 32])];
+        assert!(keys_equal(&keys, &keys.clone()));
+        assert!(!keys_equal(&keys, &keys[..1]));
+        assert!(!keys_equal(&keys, &[keys[1], keys[0]]));
+    }
+
     #[test]
     fn compare_string_equal() {
         let one = "some string".to_string();
@@ -131,6 +205,31 @@ mod test {
         assert!(slice_equal(&data, &result));
     }
 
+    #[test]
+    fn check_key_length() {
+        assert!(check_length(32, 32).is_ok());
+        match check_length(31, 32) {
+            Err(::Error::InvalidKeyLength { expected, actual }) => {
+                assert_eq!(expected, 32);
+                assert_eq!(actual, 31);
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn parse_type_tags() {
+        assert_eq!(parse_type_tag(::data_tags::MAID_TAG.to_string().into_bytes()).unwrap(), ::data_tags::MAID_TAG);
+        assert!(parse_type_tag("not a tag".to_string().into_bytes()).is_err());
+        assert!(parse_type_tag(vec![0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn decode_error() {
+        assert_eq!(::Error::BadTypeTag.decode_description(), "maidsafe_types::Error::BadTypeTag".to_string());
+        assert_eq!(::Error::UnknownTag(7).decode_description(), "maidsafe_types::Error::UnknownTag(7)".to_string());
+    }
+
     #[test]
     fn copy_strings_to_bad_array() {
         let one = "some string".to_string();
@@ -0,0 +1,142 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use sodiumoxide::crypto;
+use std::mem;
+use IdTypeTags;
+use super::revocation_id_type::*;
+use super::id_type::*;
+use super::public_id_type::*;
+
+/// Derives the RevocationIdType, IdType and PublicIdType belonging to a user's keyword, pin and
+/// password.
+///
+/// The credentials are passed through scrypt, so the same credentials always regenerate the same
+/// three ids.  The type tags are part of the salt, so the Maid and Mpid ids derived from the same
+/// credentials are unrelated.  The work factors are fixed; changing them would change every
+/// derived id.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::MaidTypeTags;
+/// use maidsafe_types::id::derive_from_credentials;
+///
+/// let (an_maid, maid, public_maid) =
+///     derive_from_credentials::<MaidTypeTags>(b"keyword", 1234, b"password").unwrap();
+/// let (an_maid_again, maid_again, public_maid_again) =
+///     derive_from_credentials::<MaidTypeTags>(b"keyword", 1234, b"password").unwrap();
+/// assert_eq!(an_maid, an_maid_again);
+/// assert_eq!(maid, maid_again);
+/// assert_eq!(public_maid, public_maid_again);
+/// ```
+pub fn derive_from_credentials<TypeTags>(keyword: &[u8], pin: u32, password: &[u8])
+        -> Result<(RevocationIdType, IdType, PublicIdType), ::Error> where TypeTags: IdTypeTags {
+    let type_tags: TypeTags = unsafe { mem::uninitialized() };
+    let salt = credentials_salt(type_tags.revocation_id_type_tag(), keyword, pin);
+    let mut key = [0u8; 2 * crypto::sign::SEEDBYTES];
+    try!(crypto::pwhash::derive_key(&mut key, password, &salt,
+                                    crypto::pwhash::OPSLIMIT_INTERACTIVE,
+                                    crypto::pwhash::MEMLIMIT_INTERACTIVE)
+         .map_err(|_| ::Error::KeyDerivation));
+
+    let revocation_seed_vec = key[..crypto::sign::SEEDBYTES].to_vec();
+    let id_seed_vec = key[crypto::sign::SEEDBYTES..].to_vec();
+    let revocation_seed = convert_to_array!(revocation_seed_vec, crypto::sign::SEEDBYTES);
+    let id_seed = convert_to_array!(id_seed_vec, crypto::sign::SEEDBYTES);
+    let revocation_id = RevocationIdType::from_seed::<TypeTags>(&crypto::sign::Seed(revocation_seed.unwrap()));
+    let id = IdType::from_seed(&revocation_id, &crypto::sign::Seed(id_seed.unwrap()));
+    let public_id = PublicIdType::new(&id, &revocation_id);
+    Ok((revocation_id, id, public_id))
+}
+
+// The type tag and keyword are hashed before the pin is appended so that no two (type tag, keyword,
+// pin) triples share a salt
+fn credentials_salt(type_tag: u64, keyword: &[u8], pin: u32) -> crypto::pwhash::Salt {
+    let mut combined = crypto::hash::sha256::hash(type_tag.to_string().as_bytes()).0.to_vec();
+    combined.extend(crypto::hash::sha256::hash(keyword).0.iter().cloned());
+    combined.extend(pin.to_string().into_bytes().into_iter());
+    let salt_vec = crypto::hash::sha256::hash(&combined).0[..crypto::pwhash::SALTBYTES].to_vec();
+    crypto::pwhash::Salt(convert_to_array!(salt_vec, crypto::pwhash::SALTBYTES).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use routing::sendable::Sendable;
+    use rustc_serialize::hex::ToHex;
+    use MaidTypeTags;
+    use MpidTypeTags;
+
+    #[test]
+    fn derive() {
+        let (an_maid, maid, public_maid) =
+            derive_from_credentials::<MaidTypeTags>(b"keyword", 1234, b"password").unwrap();
+        assert_eq!(*an_maid.type_tag(), ::data_tags::AN_MAID_TAG);
+        assert!(public_maid.verify());
+        assert_eq!(public_maid.public_keys().0 .0, maid.public_keys().0 .0);
+
+        let (an_maid_again, maid_again, public_maid_again) =
+            derive_from_credentials::<MaidTypeTags>(b"keyword", 1234, b"password").unwrap();
+        assert_eq!(an_maid, an_maid_again);
+        assert_eq!(maid, maid_again);
+        assert_eq!(public_maid, public_maid_again);
+        assert_eq!(public_maid.name(), public_maid_again.name());
+
+        // Changing any credential gives unrelated ids
+        for &(keyword, pin, password) in [(&b"keyword"[..], 1235u32, &b"password"[..]),
+                                          (&b"keywore"[..], 1234u32, &b"password"[..]),
+                                          (&b"keyword"[..], 1234u32, &b"passwore"[..])].iter() {
+            let (other_an_maid, other_maid, _) =
+                derive_from_credentials::<MaidTypeTags>(keyword, pin, password).unwrap();
+            assert!(other_an_maid != an_maid);
+            assert!(other_maid != maid);
+        }
+
+        // Maid and Mpid ids derived from the same credentials cannot be linked by their keys
+        let (an_mpid, mpid, public_mpid) =
+            derive_from_credentials::<MpidTypeTags>(b"keyword", 1234, b"password").unwrap();
+        assert_eq!(*an_mpid.type_tag(), ::data_tags::AN_MPID_TAG);
+        assert!(an_mpid.public_key().0 != an_maid.public_key().0);
+        assert!(mpid.public_keys().0 .0 != maid.public_keys().0 .0);
+        assert!(mpid.public_keys().1 .0 != maid.public_keys().1 .0);
+        assert!(public_mpid.revocation_public_key().0 != public_maid.revocation_public_key().0);
+    }
+
+    #[test]
+    fn known_answer() {
+        // Changing the salt, the scrypt work factors or the seed derivation would change these,
+        // and users could no longer recover their ids from their credentials
+        let (an_maid, maid, public_maid) =
+            derive_from_credentials::<MaidTypeTags>(b"keyword", 1234, b"password").unwrap();
+        assert_eq!(an_maid.public_key().0.to_hex(),
+                   "ba5688db47c611ccd66c682c408f30953b88123b0dea94c46769432733e74e0a".to_string());
+        assert_eq!(maid.public_keys().0 .0.to_hex(),
+                   "80c8cd43ca038b5b8b1fa5a72be3a4b9a255c01d1f60d5f14a270c8cedd1b2c9".to_string());
+        assert_eq!(maid.public_keys().1 .0.to_hex(),
+                   "9a967a28c26c327b60e451529464fb95a997cd136dee6a45ea6f92a2396ccb5a".to_string());
+        assert_eq!(public_maid.revocation_public_key().0, an_maid.public_key().0);
+
+        let (an_mpid, mpid, _) = derive_from_credentials::<MpidTypeTags>(b"keyword", 1234, b"password").unwrap();
+        assert_eq!(an_mpid.public_key().0.to_hex(),
+                   "a92a4a941014f8c32d49bcd51e3d4552090f76cf5b7bed6de87e2941da7319c2".to_string());
+        assert_eq!(mpid.public_keys().0 .0.to_hex(),
+                   "8b3b482cef5ef9b89b3c9d8409ec0e1367ecfac89ae854a8d34f8d934b84578f".to_string());
+        assert_eq!(mpid.public_keys().1 .0.to_hex(),
+                   "bce12cf91838ff7d8f05ca34ff55acb97b77e1092a95f2f64a37cbf152148405".to_string());
+    }
+}
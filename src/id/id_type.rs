@@ -15,16 +15,24 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use cbor;
 use cbor::CborTagEncode;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use sodiumoxide::crypto;
 use helper::*;
 use super::revocation_id_type::*;
+use super::secret::Secret;
 use std::fmt;
+use std::io::{Read, Write};
 use routing::NameType;
 
 /// IdType
 ///
+/// The secret keys never leave the IdType except through `serialise_with_secret_keys` or
+/// `to_json_with_secret_keys`, and are zeroed in memory when it is dropped.
+///
 /// #Examples
 /// ```
 /// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags};
@@ -32,11 +40,10 @@ use routing::NameType;
 /// let maid: IdType  = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
 ///
 /// ```
-#[derive(Clone)]
 pub struct IdType {
     type_tag: u64,
     public_keys: (crypto::sign::PublicKey, crypto::box_::PublicKey),
-    secret_keys: (crypto::sign::SecretKey, crypto::box_::SecretKey)
+    secret_keys: Secret<(crypto::sign::SecretKey, crypto::box_::SecretKey)>
 }
 
 impl IdType {
@@ -48,37 +55,48 @@ impl IdType {
         IdType {
             type_tag: revocation_id.type_tags().1,
             public_keys: (signing_keys.0, asym_keys.0),
-            secret_keys: (signing_keys.1, asym_keys.1)
+            secret_keys: Secret::new((signing_keys.1, asym_keys.1))
+        }
+    }
+    /// Creates an IdType whose keys are derived from seed.  The same RevocationIdType and seed
+    /// always give the same IdType.
+    pub fn from_seed(revocation_id: &RevocationIdType, seed: &crypto::sign::Seed) -> IdType {
+        let signing_keys = crypto::sign::keypair_from_seed(seed);
+        // Derive the encryption key from a hash of the seed so it is unrelated to the signing key
+        let sec_asym_key = crypto::box_::SecretKey(crypto::hash::sha256::hash(&seed.0).0);
+        let pub_asym_key = crypto::box_::PublicKey(crypto::scalarmult::curve25519::scalarmult_base(
+            &crypto::scalarmult::curve25519::Scalar(sec_asym_key.0)).0);
+
+        IdType {
+            type_tag: revocation_id.type_tags().1,
+            public_keys: (signing_keys.0, pub_asym_key),
+            secret_keys: Secret::new((signing_keys.1, sec_asym_key))
         }
     }
     /// Returns name
     pub fn name(&self) -> NameType {
-        let combined_iter = (&self.public_keys.0).0.into_iter().chain((&self.public_keys.1).0.into_iter());
-        let mut combined: Vec<u8> = Vec::new();
-        for iter in combined_iter {
-            combined.push(*iter);
-        }
-        for i in self.type_tag.to_string().into_bytes().into_iter() {
-            combined.push(i);
-        }
-        NameType(crypto::hash::sha512::hash(&combined).0)
+        compute_name(&self.public_keys, self.type_tag)
+    }
+    /// Returns a copy of the IdType holding only its public keys
+    pub fn public_part(&self) -> IdTypePublicPart {
+        IdTypePublicPart { type_tag: self.type_tag, public_keys: self.public_keys }
     }
     /// Returns the PublicKeys
     pub fn public_keys(&self) -> &(crypto::sign::PublicKey, crypto::box_::PublicKey){
         &self.public_keys
     }
-    /// Returns the PublicKeys
-    pub fn secret_keys(&self) -> &(crypto::sign::SecretKey, crypto::box_::SecretKey) {
-        &self.secret_keys
-    }
     /// Signs the data with the SecretKey and returns the Signed data
     pub fn sign(&self, data : &[u8]) -> Vec<u8> {
-        return crypto::sign::sign(&data, &self.secret_keys.0)
+        return crypto::sign::sign(&data, &self.secret_keys.get().0)
+    }
+    /// Signs the data with the SecretKey and returns only the Signature
+    pub fn sign_detached(&self, data: &[u8]) -> crypto::sign::Signature {
+        crypto::sign::sign_detached(data, &self.secret_keys.get().0)
     }
     /// Encrypts and authenticates data. It returns a ciphertext and the Nonce.
     pub fn seal(&self, data : &[u8], to : &crypto::box_::PublicKey) -> (Vec<u8>, crypto::box_::Nonce) {
         let nonce = crypto::box_::gen_nonce();
-        let sealed = crypto::box_::seal(data, &nonce, &to, &self.secret_keys.1);
+        let sealed = crypto::box_::seal(data, &nonce, &to, &self.secret_keys.get().1);
         return (sealed, nonce);
     }
     /// Verifies and decrypts the data
@@ -86,11 +104,92 @@ impl IdType {
         &self,
         data : &[u8],
         nonce : &crypto::box_::Nonce,
-        from : &crypto::box_::PublicKey) -> Result<Vec<u8>, ::CryptoError> {
-        return crypto::box_::open(&data, &nonce, &from, &self.secret_keys.1).ok_or(::CryptoError::Unknown);
+        from : &crypto::box_::PublicKey) -> Result<Vec<u8>, ::Error> {
+        return crypto::box_::open(&data, &nonce, &from, &self.secret_keys.get().1).ok_or(::Error::Decryption);
+    }
+    /// Encrypts and authenticates everything read from reader, writing the result to writer in
+    /// frames which `open_stream` can decrypt one at a time.
+    pub fn seal_stream<R: Read, W: Write>(&self, reader: &mut R, writer: &mut W,
+                                          to: &crypto::box_::PublicKey) -> Result<(), ::Error> {
+        let key = crypto::box_::precompute(to, &self.secret_keys.get().1);
+        super::stream::seal(&key, reader, writer)
+    }
+    /// Verifies and decrypts a stream produced by `seal_stream`.  Decrypted data is written as it
+    /// is verified, so on error anything already written to writer must be discarded.
+    pub fn open_stream<R: Read, W: Write>(&self, reader: &mut R, writer: &mut W,
+                                          from: &crypto::box_::PublicKey) -> Result<(), ::Error> {
+        let key = crypto::box_::precompute(from, &self.secret_keys.get().1);
+        super::stream::open(&key, reader, writer)
+    }
+    /// Decrypts data produced by `PublicIdType::seal_anonymous`
+    pub fn open_anonymous(&self, sealed: &[u8]) -> Result<Vec<u8>, ::Error> {
+        let header_len = crypto::box_::PUBLICKEYBYTES + crypto::box_::NONCEBYTES;
+        if sealed.len() < header_len + crypto::box_::MACBYTES {
+            return Err(::Error::Decryption);
+        }
+        let ephemeral_public_key_vec = sealed[..crypto::box_::PUBLICKEYBYTES].to_vec();
+        let nonce_vec = sealed[crypto::box_::PUBLICKEYBYTES..header_len].to_vec();
+        let ephemeral_public_key = crypto::box_::PublicKey(convert_to_array!(ephemeral_public_key_vec, crypto::box_::PUBLICKEYBYTES).unwrap());
+        let nonce = crypto::box_::Nonce(convert_to_array!(nonce_vec, crypto::box_::NONCEBYTES).unwrap());
+        self.open(&sealed[header_len..], &nonce, &ephemeral_public_key)
+    }
+    /// Cbor encodes the IdType including its secret keys.  IdType deliberately does not implement
+    /// Encodable; use `public_part` where the secret keys are not needed.
+    pub fn serialise_with_secret_keys(&self) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&WithSecretKeys(self)]).unwrap();
+        e.into_bytes()
+    }
+    /// Returns the JSON representation of the IdType including its secret keys.  IdType
+    /// deliberately does not implement ToJson.
+    pub fn to_json_with_secret_keys(&self) -> Json {
+        let &(ref sec_sign, ref sec_asym) = self.secret_keys.get();
+        ObjectBuilder::new(self.type_tag)
+            .bytes("public_sign_key", &self.public_keys.0 .0)
+            .bytes("public_encrypt_key", &self.public_keys.1 .0)
+            .bytes("secret_sign_key", &sec_sign.0)
+            .bytes("secret_encrypt_key", &sec_asym.0)
+            .build()
+    }
+    /// Reads an IdType from the output of `to_json_with_secret_keys`
+    pub fn from_json_with_secret_keys(json: &Json) -> Result<IdType, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::MAID_TAG, ::data_tags::MPID_TAG]));
+        let pub_sign_vec = try!(object.fixed_bytes("public_sign_key", crypto::sign::PUBLICKEYBYTES));
+        let pub_asym_vec = try!(object.fixed_bytes("public_encrypt_key", crypto::box_::PUBLICKEYBYTES));
+        let sec_sign_vec = try!(object.fixed_bytes("secret_sign_key", crypto::sign::SECRETKEYBYTES));
+        let sec_asym_vec = try!(object.fixed_bytes("secret_encrypt_key", crypto::box_::SECRETKEYBYTES));
+        Ok(IdType{ type_tag: try!(object.u64("tag")),
+            public_keys: (crypto::sign::PublicKey(convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES).unwrap()),
+                          crypto::box_::PublicKey(convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES).unwrap())),
+            secret_keys: Secret::new((crypto::sign::SecretKey(convert_to_array!(sec_sign_vec, crypto::sign::SECRETKEYBYTES).unwrap()),
+                                      crypto::box_::SecretKey(convert_to_array!(sec_asym_vec, crypto::box_::SECRETKEYBYTES).unwrap()))) })
+    }
+    /// Encodes the IdType, including its secret keys, and encrypts it under a key derived from
+    /// password.  The result can be safely written to disk.
+    pub fn export_encrypted(&self, password: &[u8]) -> Result<Vec<u8>, ::Error> {
+        super::keystore::seal(&self.serialise_with_secret_keys(), password)
+    }
+    /// Recovers an IdType from the output of `export_encrypted`
+    pub fn import_encrypted(keystore: &[u8], password: &[u8]) -> Result<IdType, ::Error> {
+        match try!(::AnyData::from_bytes(&try!(super::keystore::open(keystore, password)))) {
+            ::AnyData::Maid(id_type) | ::AnyData::Mpid(id_type) => Ok(id_type),
+            _ => Err(::Error::BadTypeTag),
+        }
     }
 }
 
+fn compute_name(public_keys: &(crypto::sign::PublicKey, crypto::box_::PublicKey), type_tag: u64) -> NameType {
+    let combined_iter = (&public_keys.0).0.into_iter().chain((&public_keys.1).0.into_iter());
+    let mut combined: Vec<u8> = Vec::new();
+    for iter in combined_iter {
+        combined.push(*iter);
+    }
+    for i in type_tag.to_string().into_bytes().into_iter() {
+        combined.push(i);
+    }
+    NameType(crypto::hash::sha512::hash(&combined).0)
+}
+
 impl PartialEq for IdType {
     fn eq(&self, other: &IdType) -> bool {
         // Private keys are mathematically linked, so just check public keys
@@ -106,13 +205,16 @@ impl fmt::Debug for IdType {
     }
 }
 
-impl Encodable for IdType {
+// Only reachable through IdType::serialise_with_secret_keys
+struct WithSecretKeys<'a>(&'a IdType);
+
+impl<'a> Encodable for WithSecretKeys<'a> {
     fn encode<E: Encoder>(&self, e: &mut E)->Result<(), E::Error> {
-        let (crypto::sign::PublicKey(pub_sign_vec), crypto::box_::PublicKey(pub_asym_vec)) = self.public_keys;
-        let (crypto::sign::SecretKey(sec_sign_vec), crypto::box_::SecretKey(sec_asym_vec)) = self.secret_keys;
-        let type_vec = self.type_tag.to_string().into_bytes();
+        let (crypto::sign::PublicKey(pub_sign_vec), crypto::box_::PublicKey(pub_asym_vec)) = self.0.public_keys;
+        let &(crypto::sign::SecretKey(ref sec_sign_vec), crypto::box_::SecretKey(ref sec_asym_vec)) = self.0.secret_keys.get();
+        let type_vec = self.0.type_tag.to_string().into_bytes();
 
-        CborTagEncode::new(self.type_tag, &(
+        CborTagEncode::new(self.0.type_tag, &(
             type_vec,
             pub_sign_vec.as_ref(),
             pub_asym_vec.as_ref(),
@@ -124,28 +226,118 @@ impl Encodable for IdType {
 impl Decodable for IdType {
     fn decode<D: Decoder>(d: &mut D)-> Result<IdType, D::Error> {
         let (tag_type_vec, pub_sign_vec, pub_asym_vec, sec_sign_vec, sec_asym_vec) : (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        try_decode!(d, check_length(pub_sign_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        try_decode!(d, check_length(pub_asym_vec.len(), crypto::box_::PUBLICKEYBYTES));
+        try_decode!(d, check_length(sec_sign_vec.len(), crypto::sign::SECRETKEYBYTES));
+        try_decode!(d, check_length(sec_asym_vec.len(), crypto::box_::SECRETKEYBYTES));
         let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
         let pub_asym_arr = convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES);
         let sec_sign_arr = convert_to_array!(sec_sign_vec, crypto::sign::SECRETKEYBYTES);
         let sec_asym_arr = convert_to_array!(sec_asym_vec, crypto::box_::SECRETKEYBYTES);
 
-        if pub_sign_arr.is_none() || pub_asym_arr.is_none() || sec_sign_arr.is_none() || sec_asym_arr.is_none() {
-            return Err(d.error("Bad IdType size"));
-        }
-
-        let type_tag: u64 = match String::from_utf8(tag_type_vec) {
-            Ok(string) =>  {
-                match string.parse::<u64>() {
-                    Ok(type_tag) => type_tag,
-                    Err(_) => return Err(d.error("Bad Tag Type"))
-                }
-            },
-            Err(_) => return Err(d.error("Bad Tag Type"))
-        };
+        let type_tag = try_decode!(d, parse_type_tag(tag_type_vec));
 
         Ok(IdType{ type_tag: type_tag,
             public_keys:(crypto::sign::PublicKey(pub_sign_arr.unwrap()), crypto::box_::PublicKey(pub_asym_arr.unwrap())),
-            secret_keys: (crypto::sign::SecretKey(sec_sign_arr.unwrap()), crypto::box_::SecretKey(sec_asym_arr.unwrap())) })
+            secret_keys: Secret::new((crypto::sign::SecretKey(sec_sign_arr.unwrap()), crypto::box_::SecretKey(sec_asym_arr.unwrap()))) })
+    }
+}
+
+/// IdTypePublicPart
+///
+/// The public keys of an IdType, safe to encode wherever the IdType needs to be referred to.
+///
+/// #Examples
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags};
+///
+/// let maid = IdType::new(&RevocationIdType::new::<MaidTypeTags>());
+/// let public_part = maid.public_part();
+/// assert_eq!(public_part.name(), maid.name());
+/// ```
+#[derive(Clone)]
+pub struct IdTypePublicPart {
+    type_tag: u64,
+    public_keys: (crypto::sign::PublicKey, crypto::box_::PublicKey),
+}
+
+impl IdTypePublicPart {
+    /// Returns the name of the IdType
+    pub fn name(&self) -> NameType {
+        compute_name(&self.public_keys, self.type_tag)
+    }
+    /// Returns the type tag of the IdType
+    pub fn type_tag(&self) -> u64 {
+        self.type_tag
+    }
+    /// Returns the PublicKeys
+    pub fn public_keys(&self) -> &(crypto::sign::PublicKey, crypto::box_::PublicKey) {
+        &self.public_keys
+    }
+    /// Returns true if signature was made over data by the IdType
+    pub fn verify_detached(&self, data: &[u8], signature: &crypto::sign::Signature) -> bool {
+        crypto::sign::verify_detached(signature, data, &self.public_keys.0)
+    }
+}
+
+impl PartialEq for IdTypePublicPart {
+    fn eq(&self, other: &IdTypePublicPart) -> bool {
+        &self.type_tag == &other.type_tag &&
+        slice_equal(&self.public_keys.0 .0, &other.public_keys.0 .0) &&
+        slice_equal(&self.public_keys.1 .0, &other.public_keys.1 .0)
+    }
+}
+
+impl fmt::Debug for IdTypePublicPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IdTypePublicPart {{ type_tag:{}, public_keys: ({:?}, {:?}) }}", self.type_tag, self.public_keys.0 .0.to_vec(), self.public_keys.1 .0.to_vec())
+    }
+}
+
+impl Encodable for IdTypePublicPart {
+    fn encode<E: Encoder>(&self, e: &mut E)->Result<(), E::Error> {
+        let (crypto::sign::PublicKey(pub_sign_vec), crypto::box_::PublicKey(pub_asym_vec)) = self.public_keys;
+        let type_vec = self.type_tag.to_string().into_bytes();
+
+        CborTagEncode::new(::data_tags::ID_PUBLIC_PART_TAG, &(
+            type_vec,
+            pub_sign_vec.as_ref(),
+            pub_asym_vec.as_ref())).encode(e)
+    }
+}
+
+impl Decodable for IdTypePublicPart {
+    fn decode<D: Decoder>(d: &mut D)-> Result<IdTypePublicPart, D::Error> {
+        let (tag_type_vec, pub_sign_vec, pub_asym_vec) : (Vec<u8>, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        try_decode!(d, check_length(pub_sign_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        try_decode!(d, check_length(pub_asym_vec.len(), crypto::box_::PUBLICKEYBYTES));
+        let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
+        let pub_asym_arr = convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES);
+
+        let type_tag = try_decode!(d, parse_type_tag(tag_type_vec));
+
+        Ok(IdTypePublicPart{ type_tag: type_tag,
+            public_keys:(crypto::sign::PublicKey(pub_sign_arr.unwrap()), crypto::box_::PublicKey(pub_asym_arr.unwrap())) })
+    }
+}
+
+impl ToJson for IdTypePublicPart {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::ID_PUBLIC_PART_TAG)
+            .field("type_tag", self.type_tag)
+            .bytes("public_sign_key", &self.public_keys.0 .0)
+            .bytes("public_encrypt_key", &self.public_keys.1 .0)
+            .build()
+    }
+}
+
+impl FromJson for IdTypePublicPart {
+    fn from_json(json: &Json) -> Result<IdTypePublicPart, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::ID_PUBLIC_PART_TAG]));
+        let pub_asym_vec = try!(object.fixed_bytes("public_encrypt_key", crypto::box_::PUBLICKEYBYTES));
+        Ok(IdTypePublicPart{ type_tag: try!(object.u64("type_tag")),
+            public_keys: (try!(object.public_key("public_sign_key")),
+                          crypto::box_::PublicKey(convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES).unwrap())) })
     }
 }
 
@@ -156,7 +348,9 @@ mod test {
     use super::*;
     use self::rand::Rng;
     use cbor;
+    use json;
     use super::super::RevocationIdType;
+    use rustc_serialize::hex::ToHex;
     use sodiumoxide::crypto;
     use Random;
     use MaidTypeTags;
@@ -172,16 +366,15 @@ mod test {
         use helper::*;
         let obj_before = IdType::generate_random();
 
-        let mut e = cbor::Encoder::from_memory();
-        e.encode(&[&obj_before]).unwrap();
+        let serialised = obj_before.serialise_with_secret_keys();
 
-        let mut d = cbor::Decoder::from_bytes(e.as_bytes());
+        let mut d = cbor::Decoder::from_bytes(&serialised[..]);
         match d.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::Maid(obj_after) => {
+            ::AnyData::Maid(obj_after) => {
                 let &(crypto::sign::PublicKey(pub_sign_arr_before), crypto::box_::PublicKey(pub_asym_arr_before)) = obj_before.public_keys();
                 let &(crypto::sign::PublicKey(pub_sign_arr_after), crypto::box_::PublicKey(pub_asym_arr_after)) = obj_after.public_keys();
-                let &(crypto::sign::SecretKey(sec_sign_arr_before), crypto::box_::SecretKey(sec_asym_arr_before)) = &obj_before.secret_keys;
-                let &(crypto::sign::SecretKey(sec_sign_arr_after), crypto::box_::SecretKey(sec_asym_arr_after)) = &obj_after.secret_keys;
+                let &(crypto::sign::SecretKey(sec_sign_arr_before), crypto::box_::SecretKey(sec_asym_arr_before)) = obj_before.secret_keys.get();
+                let &(crypto::sign::SecretKey(sec_sign_arr_after), crypto::box_::SecretKey(sec_asym_arr_after)) = obj_after.secret_keys.get();
 
                 assert_eq!(pub_sign_arr_before, pub_sign_arr_after);
                 assert_eq!(pub_asym_arr_before, pub_asym_arr_after);
@@ -192,14 +385,171 @@ mod test {
         }
     }
 
+#[test]
+    fn json_maid() {
+        let obj_before = IdType::generate_random();
+        let json = obj_before.to_json_with_secret_keys().to_string();
+        let obj_after = IdType::from_json_with_secret_keys(&json::parse(&json).unwrap()).unwrap();
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(obj_before.serialise_with_secret_keys(), obj_after.serialise_with_secret_keys());
+
+        let signature = obj_after.sign_detached(b"data");
+        assert!(obj_before.public_part().verify_detached(b"data", &signature));
+    }
+
+#[test]
+    fn decode_bad_key_length() {
+        use cbor::CborTagEncode;
+
+        let tag_type_vec = ::data_tags::MAID_TAG.to_string().into_bytes();
+        let short_key = vec![0u8; crypto::sign::PUBLICKEYBYTES - 1];
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(::data_tags::MAID_TAG, &(tag_type_vec,
+                                                                 short_key,
+                                                                 vec![0u8; crypto::box_::PUBLICKEYBYTES],
+                                                                 vec![0u8; crypto::sign::SECRETKEYBYTES],
+                                                                 vec![0u8; crypto::box_::SECRETKEYBYTES]))]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::InvalidKeyLength { expected, actual }) => {
+                assert_eq!(expected, crypto::sign::PUBLICKEYBYTES);
+                assert_eq!(actual, crypto::sign::PUBLICKEYBYTES - 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+#[test]
+    fn from_seed() {
+        let an_maid = RevocationIdType::generate_random();
+        let seed = crypto::sign::Seed([3u8; crypto::sign::SEEDBYTES]);
+        let maid1 = IdType::from_seed(&an_maid, &seed);
+        let maid2 = IdType::from_seed(&an_maid, &seed);
+        assert_eq!(maid1, maid2);
+        assert!(maid1 != IdType::from_seed(&an_maid, &crypto::sign::Seed([4u8; crypto::sign::SEEDBYTES])));
+
+        // Known answers: ids already derived must keep being derived the same way
+        assert_eq!(maid1.public_keys().0 .0.to_hex(),
+                   "ed4928c628d1c2c6eae90338905995612959273a5c63f93636c14614ac8737d1".to_string());
+        assert_eq!(maid1.public_keys().1 .0.to_hex(),
+                   "3fab289af003d2b0a32321cc8ca874372062271ae7bf0c256dd4b6dd9a4ffa5e".to_string());
+
+        // The derived encryption keys must work with keys from gen_keypair
+        let maid3 = IdType::generate_random();
+        let data = vec![5u8; 100];
+        let (sealed, nonce) = maid3.seal(&data, &maid1.public_keys().1);
+        assert_eq!(maid2.open(&sealed, &nonce, &maid3.public_keys().1).unwrap(), data);
+        let (sealed, nonce) = maid1.seal(&data, &maid3.public_keys().1);
+        assert_eq!(maid3.open(&sealed, &nonce, &maid2.public_keys().1).unwrap(), data);
+    }
+
+#[test]
+    fn serialisation_public_part() {
+        let maid = IdType::generate_random();
+        let public_part = maid.public_part();
+        assert_eq!(public_part.name(), maid.name());
+        assert_eq!(public_part.type_tag(), ::data_tags::MAID_TAG);
+
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&public_part]).unwrap();
+        let encoded = e.into_bytes();
+
+        // None of the secret key bytes may appear in the encoding
+        let secret_sign_key = maid.secret_keys.get().0 .0.to_vec();
+        let secret_asym_key = maid.secret_keys.get().1 .0.to_vec();
+        assert!(!encoded.windows(secret_sign_key.len()).any(|window| window == &secret_sign_key[..]));
+        assert!(!encoded.windows(secret_asym_key.len()).any(|window| window == &secret_asym_key[..]));
+
+        match ::AnyData::from_bytes(&encoded) {
+            Ok(::AnyData::IdTypePublicPart(decoded)) => assert_eq!(decoded, public_part),
+            _ => panic!("Unexpected!"),
+        }
+
+        let obj_after = json::from_str::<IdTypePublicPart>(&json::to_string(&public_part)).unwrap();
+        assert_eq!(obj_after, public_part);
+        assert_eq!(obj_after.name(), maid.name());
+        assert!(json::from_str::<IdTypePublicPart>(&maid.to_json_with_secret_keys().to_string()).is_err());
+    }
+
+#[test]
+    fn sign_detached() {
+        let maid = IdType::generate_random();
+        let data = vec![1u8; 100];
+        let signature = maid.sign_detached(&data);
+        assert_eq!(signature.0.to_vec(), maid.sign(&data)[..crypto::sign::SIGNATUREBYTES].to_vec());
+        assert!(maid.public_part().verify_detached(&data, &signature));
+        assert!(!maid.public_part().verify_detached(&data[1..], &signature));
+        assert!(!IdType::generate_random().public_part().verify_detached(&data, &signature));
+    }
+
+#[test]
+    fn open_anonymous() {
+        use super::super::PublicIdType;
+
+        let revocation_maid = RevocationIdType::generate_random();
+        let maid = IdType::new(&revocation_maid);
+        let public_maid = PublicIdType::new(&maid, &revocation_maid);
+        let data = rand::thread_rng().gen_iter::<u8>().take(100).collect::<Vec<u8>>();
+
+        let sealed = public_maid.seal_anonymous(&data);
+        assert!(sealed != public_maid.seal_anonymous(&data));
+        assert_eq!(maid.open_anonymous(&sealed).unwrap(), data);
+        assert!(IdType::generate_random().open_anonymous(&sealed).is_err());
+        assert!(maid.open_anonymous(&sealed[1..]).is_err());
+        assert!(maid.open_anonymous(&[]).is_err());
+
+        // The layout is the ephemeral public key and nonce followed by a normal sealed message
+        let header_len = crypto::box_::PUBLICKEYBYTES + crypto::box_::NONCEBYTES;
+        let mut ephemeral_public_key = crypto::box_::PublicKey([0u8; crypto::box_::PUBLICKEYBYTES]);
+        let mut nonce = crypto::box_::Nonce([0u8; crypto::box_::NONCEBYTES]);
+        for (dst, src) in ephemeral_public_key.0.iter_mut().zip(sealed.iter()) { *dst = *src; }
+        for (dst, src) in nonce.0.iter_mut().zip(sealed[crypto::box_::PUBLICKEYBYTES..].iter()) { *dst = *src; }
+        assert_eq!(maid.open(&sealed[header_len..], &nonce, &ephemeral_public_key).unwrap(), data);
+
+        // A message from seal opens anonymously once framed the same way
+        let sender = IdType::generate_random();
+        let (sealed, nonce) = sender.seal(&data, &maid.public_keys().1);
+        let mut framed = sender.public_keys().1 .0.to_vec();
+        framed.extend(nonce.0.iter().cloned());
+        framed.extend(sealed.into_iter());
+        assert_eq!(maid.open_anonymous(&framed).unwrap(), data);
+    }
+
+#[test]
+    fn export_and_import_encrypted() {
+        let maid = IdType::generate_random();
+        let keystore = maid.export_encrypted(b"password").unwrap();
+        let imported = IdType::import_encrypted(&keystore, b"password").unwrap();
+        assert_eq!(imported, maid);
+        assert_eq!(imported.secret_keys.get().0 .0.to_vec(), maid.secret_keys.get().0 .0.to_vec());
+        assert_eq!(imported.secret_keys.get().1 .0, maid.secret_keys.get().1 .0);
+
+        match IdType::import_encrypted(&keystore, b"passwore") {
+            Err(::Error::WrongPassword) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match IdType::import_encrypted(&keystore[1..], b"password") {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        let an_maid_keystore = RevocationIdType::generate_random().export_encrypted(b"password").unwrap();
+        match IdType::import_encrypted(&an_maid_keystore, b"password") {
+            Err(::Error::BadTypeTag) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
 #[test]
     fn generation() {
         let maid1 = IdType::generate_random();
         let maid2 = IdType::generate_random();
-        let maid2_clone = maid2.clone();
+        let maid2_copy = match ::AnyData::from_bytes(&maid2.serialise_with_secret_keys()).unwrap() {
+            ::AnyData::Maid(maid) => maid,
+            _ => panic!("Unexpected!"),
+        };
 
-        assert_eq!(maid2, maid2_clone);
-        assert!(!(maid2 != maid2_clone));
+        assert_eq!(maid2, maid2_copy);
+        assert!(!(maid2 != maid2_copy));
         assert!(maid1 != maid2);
 
         let random_bytes = rand::thread_rng().gen_iter::<u8>().take(100).collect::<Vec<u8>>();
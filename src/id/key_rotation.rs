@@ -0,0 +1,376 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor;
+use cbor::CborTagEncode;
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use sodiumoxide::crypto;
+use helper::*;
+use json::{FromJson, Object, ObjectBuilder};
+use rustc_serialize::json::{Json, ToJson};
+use signable::{Signable, SignableBytes};
+use routing::NameType;
+use routing::sendable::Sendable;
+use std::fmt;
+use super::revocation_id_type::*;
+use super::id_type::*;
+use super::public_id_type::*;
+
+/// SupersededRecord
+///
+/// Signed by the revocation key when an IdType is rotated, linking the name of the PublicIdType
+/// being replaced to that of its successor.
+#[derive(Clone)]
+pub struct SupersededRecord {
+    old_name: NameType,
+    new_name: NameType,
+    sequence_number: u64,
+    revocation_public_key: crypto::sign::PublicKey,
+    signature: crypto::sign::Signature,
+}
+
+/// Signs "SupersededRecord", old name, new name, sequence number and revocation public key
+impl Signable for SupersededRecord {
+    fn signable_bytes(&self) -> Vec<u8> {
+        SignableBytes::new("SupersededRecord").bytes(&self.old_name.0).bytes(&self.new_name.0)
+            .u64(self.sequence_number).bytes(&self.revocation_public_key.0).into_bytes()
+    }
+}
+
+impl SupersededRecord {
+    /// Returns the name of the PublicIdType which has been superseded
+    pub fn old_name(&self) -> &NameType {
+        &self.old_name
+    }
+    /// Returns the name of the PublicIdType which replaces it
+    pub fn new_name(&self) -> &NameType {
+        &self.new_name
+    }
+    /// Returns the sequence number of the replacing PublicIdType
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+    /// Returns the public key of the RevocationIdType which signed the record
+    pub fn revocation_public_key(&self) -> &crypto::sign::PublicKey {
+        &self.revocation_public_key
+    }
+    /// Returns the signature
+    pub fn signature(&self) -> &crypto::sign::Signature {
+        &self.signature
+    }
+    /// Returns true if the signature was made by the revocation key
+    pub fn verify(&self) -> bool {
+        crypto::sign::verify_detached(&self.signature, &self.signable_bytes(), &self.revocation_public_key)
+    }
+}
+
+impl Sendable for SupersededRecord {
+    fn name(&self) -> NameType {
+        let mut combined = self.signable_bytes();
+        combined.extend(self.signature.0.iter().cloned());
+        NameType(crypto::hash::sha512::hash(&combined).0)
+    }
+
+    fn type_tag(&self) -> u64 {
+        ::data_tags::SUPERSEDED_ID_TAG
+    }
+
+    fn serialised_contents(&self) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&self]).unwrap();
+        e.into_bytes()
+    }
+
+    fn refresh(&self) -> bool {
+        false
+    }
+
+    fn merge(&self, _: Vec<Box<Sendable>>) -> Option<Box<Sendable>> { None }
+}
+
+impl PartialEq for SupersededRecord {
+    fn eq(&self, other: &SupersededRecord) -> bool {
+        self.old_name == other.old_name &&
+        self.new_name == other.new_name &&
+        self.sequence_number == other.sequence_number &&
+        slice_equal(&self.revocation_public_key.0, &other.revocation_public_key.0) &&
+        slice_equal(&self.signature.0, &other.signature.0)
+    }
+}
+
+impl fmt::Debug for SupersededRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SupersededRecord {{ old_name:{:?}, new_name:{:?}, sequence_number:{}, revocation_public_key:{:?}, signature:{:?} }}",
+            self.old_name, self.new_name, self.sequence_number, self.revocation_public_key.0.to_vec(),
+            self.signature.0.to_vec())
+    }
+}
+
+impl Encodable for SupersededRecord {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        CborTagEncode::new(::data_tags::SUPERSEDED_ID_TAG, &(
+            &self.old_name,
+            &self.new_name,
+            self.sequence_number,
+            self.revocation_public_key.0.as_ref(),
+            self.signature.0.as_ref())).encode(e)
+    }
+}
+
+impl Decodable for SupersededRecord {
+    fn decode<D: Decoder>(d: &mut D) -> Result<SupersededRecord, D::Error> {
+        let (old_name, new_name, sequence_number, revocation_public_key_vec, signature_vec):
+            (NameType, NameType, u64, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        try_decode!(d, check_length(revocation_public_key_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        try_decode!(d, check_length(signature_vec.len(), crypto::sign::SIGNATUREBYTES));
+        let revocation_public_key_arr = convert_to_array!(revocation_public_key_vec, crypto::sign::PUBLICKEYBYTES);
+        let signature_arr = convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES);
+
+        Ok(SupersededRecord {
+            old_name: old_name,
+            new_name: new_name,
+            sequence_number: sequence_number,
+            revocation_public_key: crypto::sign::PublicKey(revocation_public_key_arr.unwrap()),
+            signature: crypto::sign::Signature(signature_arr.unwrap()),
+        })
+    }
+}
+
+impl ToJson for SupersededRecord {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::SUPERSEDED_ID_TAG)
+            .bytes("old_name", &self.old_name.0)
+            .bytes("new_name", &self.new_name.0)
+            .field("sequence_number", self.sequence_number)
+            .bytes("revocation_public_key", &self.revocation_public_key.0)
+            .bytes("signature", &self.signature.0)
+            .build()
+    }
+}
+
+impl FromJson for SupersededRecord {
+    fn from_json(json: &Json) -> Result<SupersededRecord, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::SUPERSEDED_ID_TAG]));
+        Ok(SupersededRecord {
+            old_name: try!(object.name_type("old_name")),
+            new_name: try!(object.name_type("new_name")),
+            sequence_number: try!(object.u64("sequence_number")),
+            revocation_public_key: try!(object.public_key("revocation_public_key")),
+            signature: try!(object.signature("signature")),
+        })
+    }
+}
+
+/// Replaces the IdType behind current with a newly generated one.  Returns the new IdType, its
+/// PublicIdType with the next sequence number, and the record superseding current, both signed by
+/// the revocation_id.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags, PublicIdType};
+/// use maidsafe_types::id::{rotate_id_type, verify_rotation_chain};
+///
+/// let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+/// let maid = IdType::new(&revocation_maid);
+/// let public_maid = PublicIdType::new(&maid, &revocation_maid);
+///
+/// let (_new_maid, new_public_maid, record) = rotate_id_type(&revocation_maid, &public_maid).unwrap();
+/// assert!(verify_rotation_chain(revocation_maid.public_key(), &[public_maid, new_public_maid],
+///                               &[record]).is_ok());
+/// ```
+pub fn rotate_id_type(revocation_id: &RevocationIdType, current: &PublicIdType)
+                      -> Result<(IdType, PublicIdType, SupersededRecord), ::Error> {
+    if !slice_equal(&current.revocation_public_key().0, &revocation_id.public_key().0) ||
+       !current.verify() {
+        return Err(::Error::SignatureVerification);
+    }
+    let sequence_number = match current.sequence_number().checked_add(1) {
+        Some(sequence_number) => sequence_number,
+        None => return Err(::Error::InvalidVersion { expected: current.sequence_number(),
+                                                     actual: current.sequence_number() }),
+    };
+    let id_type = IdType::new(revocation_id);
+    let public_id = PublicIdType::new_with_sequence_number(&id_type, revocation_id, sequence_number);
+    let mut record = SupersededRecord {
+        old_name: current.name(),
+        new_name: public_id.name(),
+        sequence_number: sequence_number,
+        revocation_public_key: revocation_id.public_key().clone(),
+        signature: crypto::sign::Signature([0u8; crypto::sign::SIGNATUREBYTES]),
+    };
+    record.signature = revocation_id.sign_detached(&record.signable_bytes());
+    Ok((id_type, public_id, record))
+}
+
+/// Checks a chain of rotations back to the revocation key.  public_ids must start with the first
+/// PublicIdType (sequence number 0) and records[i] must supersede public_ids[i] with
+/// public_ids[i + 1].  Every item must be signed by revocation_public_key.
+pub fn verify_rotation_chain(revocation_public_key: &crypto::sign::PublicKey,
+                             public_ids: &[PublicIdType],
+                             records: &[SupersededRecord]) -> Result<(), ::Error> {
+    if public_ids.len() != records.len() + 1 {
+        return Err(::Error::MalformedChain);
+    }
+    for (index, public_id) in public_ids.iter().enumerate() {
+        if !slice_equal(&public_id.revocation_public_key().0, &revocation_public_key.0) || !public_id.verify() {
+            return Err(::Error::SignatureVerification);
+        }
+        if public_id.sequence_number() != index as u64 {
+            return Err(::Error::InvalidVersion { expected: index as u64, actual: public_id.sequence_number() });
+        }
+    }
+    for (record, pair) in records.iter().zip(public_ids.windows(2)) {
+        if !slice_equal(&record.revocation_public_key.0, &revocation_public_key.0) || !record.verify() {
+            return Err(::Error::SignatureVerification);
+        }
+        if record.old_name != pair[0].name() || record.new_name != pair[1].name() {
+            return Err(::Error::NameMismatch);
+        }
+        if record.sequence_number != pair[1].sequence_number() {
+            return Err(::Error::InvalidVersion { expected: pair[1].sequence_number(),
+                                                 actual: record.sequence_number });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cbor;
+    use json;
+    use super::super::{IdType, RevocationIdType, PublicIdType};
+    use MaidTypeTags;
+    use routing::NameType;
+    use rustc_serialize::hex::ToHex;
+    use signable::Signable;
+    use signable::expected::{field, number};
+    use sodiumoxide::crypto;
+
+    fn rotation_chain(rotations: usize) -> (RevocationIdType, Vec<PublicIdType>, Vec<SupersededRecord>) {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let maid = IdType::new(&revocation_maid);
+        let mut public_ids = vec![PublicIdType::new(&maid, &revocation_maid)];
+        let mut records = Vec::new();
+        for _ in 0..rotations {
+            let (_, public_id, record) = rotate_id_type(&revocation_maid, public_ids.last().unwrap()).unwrap();
+            public_ids.push(public_id);
+            records.push(record);
+        }
+        (revocation_maid, public_ids, records)
+    }
+
+    #[test]
+    fn rotation() {
+        let (revocation_maid, public_ids, records) = rotation_chain(3);
+        assert_eq!(public_ids[3].sequence_number(), 3);
+        assert!(public_ids.iter().all(|public_id| public_id.verify()));
+        assert!(records.iter().all(|record| record.verify()));
+        assert!(verify_rotation_chain(revocation_maid.public_key(), &public_ids, &records).is_ok());
+
+        // Only the revocation id of the current public id may rotate it
+        let other_revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        assert!(rotate_id_type(&other_revocation_maid, &public_ids[3]).is_err());
+    }
+
+    #[test]
+    fn broken_chain() {
+        let (revocation_maid, public_ids, records) = rotation_chain(2);
+        let other_revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+
+        // Wrong revocation key
+        assert!(verify_rotation_chain(other_revocation_maid.public_key(), &public_ids, &records).is_err());
+
+        // Missing link
+        match verify_rotation_chain(revocation_maid.public_key(), &public_ids[1..], &records[1..]) {
+            Err(::Error::InvalidVersion { expected, actual }) => {
+                assert_eq!(expected, 0);
+                assert_eq!(actual, 1);
+            },
+            _ => panic!("Unexpected!"),
+        }
+        match verify_rotation_chain(revocation_maid.public_key(), &public_ids, &records[..1]) {
+            Err(::Error::MalformedChain) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match verify_rotation_chain(revocation_maid.public_key(), &[], &[]) {
+            Err(::Error::MalformedChain) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Records out of order
+        let swapped = vec![records[1].clone(), records[0].clone()];
+        assert!(verify_rotation_chain(revocation_maid.public_key(), &public_ids, &swapped).is_err());
+
+        // Forged record
+        let mut forged = records.clone();
+        forged[1].new_name = forged[0].new_name.clone();
+        match verify_rotation_chain(revocation_maid.public_key(), &public_ids, &forged) {
+            Err(::Error::SignatureVerification) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn signable_bytes() {
+        let (revocation_maid, _, records) = rotation_chain(1);
+        let mut expected = field(b"SupersededRecord");
+        expected.extend(field(&records[0].old_name().0));
+        expected.extend(field(&records[0].new_name().0));
+        expected.extend(number(1));
+        expected.extend(field(&revocation_maid.public_key().0));
+        assert_eq!(records[0].signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(records[0].signature(), &expected,
+                                              revocation_maid.public_key()));
+
+        let fixed = SupersededRecord { old_name: NameType([3u8; 64]),
+                                       new_name: NameType([4u8; 64]),
+                                       sequence_number: 1,
+                                       revocation_public_key: crypto::sign::PublicKey([7u8; 32]),
+                                       signature: crypto::sign::Signature([8u8; 64]),
+                                     };
+        assert_eq!(fixed.signable_bytes().to_hex(),
+                   concat!("0000000000000010537570657273656465645265636f726400000000000000400303030303030303",
+                           "03030303030303030303030303030303030303030303030303030303030303030303030303030303",
+                           "03030303030303030303030303030303000000000000004004040404040404040404040404040404",
+                           "04040404040404040404040404040404040404040404040404040404040404040404040404040404",
+                           "04040404040404040000000000000001000000000000002007070707070707070707070707070707",
+                           "07070707070707070707070707070707"));
+    }
+
+    #[test]
+    fn serialisation_superseded_record() {
+        let (_, _, records) = rotation_chain(1);
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&records[0]]).unwrap();
+
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::SupersededRecord(decoded)) => assert_eq!(decoded, records[0]),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn json_superseded_record() {
+        let (revocation_maid, public_ids, records) = rotation_chain(1);
+        let obj_after = json::from_str::<SupersededRecord>(&json::to_string(&records[0])).unwrap();
+        assert_eq!(obj_after, records[0]);
+        assert!(verify_rotation_chain(revocation_maid.public_key(), &public_ids, &[obj_after]).is_ok());
+        assert!(json::from_str::<SupersededRecord>(&json::to_string(&public_ids[0])).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Password protected storage for secret key material.
+//!
+//! A keystore is laid out as
+//!
+//! ```text
+//! salt | nonce | ciphertext | checksum
+//! ```
+//!
+//! The secretbox key is derived from the password and salt using scrypt.  The checksum is the
+//! sha256 of everything before it, so a damaged keystore can be told apart from a wrong password:
+//! only once the checksum matches is a failure to decrypt blamed on the password.
+
+use sodiumoxide::crypto;
+use helper::*;
+
+const CHECKSUM_BYTES: usize = crypto::hash::sha256::HASHBYTES;
+const HEADER_BYTES: usize = crypto::pwhash::SALTBYTES + crypto::secretbox::NONCEBYTES;
+
+fn derive_key(password: &[u8], salt: &crypto::pwhash::Salt) -> Result<crypto::secretbox::Key, ::Error> {
+    let mut key = crypto::secretbox::Key([0u8; crypto::secretbox::KEYBYTES]);
+    {
+        let crypto::secretbox::Key(ref mut key_bytes) = key;
+        try!(crypto::pwhash::derive_key(key_bytes, password, salt,
+                                        crypto::pwhash::OPSLIMIT_INTERACTIVE,
+                                        crypto::pwhash::MEMLIMIT_INTERACTIVE)
+             .map_err(|_| ::Error::KeyDerivation));
+    }
+    Ok(key)
+}
+
+/// Encrypts plain_text under a key derived from password
+pub fn seal(plain_text: &[u8], password: &[u8]) -> Result<Vec<u8>, ::Error> {
+    let salt = crypto::pwhash::gen_salt();
+    let nonce = crypto::secretbox::gen_nonce();
+    let key = try!(derive_key(password, &salt));
+
+    let mut keystore = salt.0.to_vec();
+    keystore.extend(nonce.0.iter().cloned());
+    keystore.extend(crypto::secretbox::seal(plain_text, &nonce, &key).into_iter());
+    let checksum = crypto::hash::sha256::hash(&keystore);
+    keystore.extend(checksum.0.iter().cloned());
+    Ok(keystore)
+}
+
+/// Decrypts a keystore produced by `seal`
+pub fn open(keystore: &[u8], password: &[u8]) -> Result<Vec<u8>, ::Error> {
+    if keystore.len() < HEADER_BYTES + crypto::secretbox::MACBYTES + CHECKSUM_BYTES {
+        return Err(::Error::CorruptedData);
+    }
+    let (contents, checksum) = keystore.split_at(keystore.len() - CHECKSUM_BYTES);
+    if !slice_equal(&crypto::hash::sha256::hash(contents).0, checksum) {
+        return Err(::Error::CorruptedData);
+    }
+
+    let salt_vec = contents[..crypto::pwhash::SALTBYTES].to_vec();
+    let nonce_vec = contents[crypto::pwhash::SALTBYTES..HEADER_BYTES].to_vec();
+    let salt = crypto::pwhash::Salt(convert_to_array!(salt_vec, crypto::pwhash::SALTBYTES).unwrap());
+    let nonce = crypto::secretbox::Nonce(convert_to_array!(nonce_vec, crypto::secretbox::NONCEBYTES).unwrap());
+    let key = try!(derive_key(password, &salt));
+    crypto::secretbox::open(&contents[HEADER_BYTES..], &nonce, &key).ok_or(::Error::WrongPassword)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seal_and_open() {
+        let plain_text = vec![9u8; 200];
+        let keystore = seal(&plain_text, b"password").unwrap();
+        assert_eq!(open(&keystore, b"password").unwrap(), plain_text);
+
+        // Sealing twice uses a fresh salt and nonce
+        assert!(seal(&plain_text, b"password").unwrap() != keystore);
+
+        match open(&keystore, b"passwore") {
+            Err(::Error::WrongPassword) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let keystore = seal(&[9u8; 200], b"password").unwrap();
+        for index in [0, HEADER_BYTES, keystore.len() - 1].iter() {
+            let mut corrupted = keystore.clone();
+            corrupted[*index] ^= 1;
+            match open(&corrupted, b"password") {
+                Err(::Error::CorruptedData) => (),
+                _ => panic!("Unexpected!"),
+            }
+        }
+        match open(&keystore[..keystore.len() - 1], b"password") {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+        match open(&[], b"password") {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
@@ -21,10 +21,23 @@ pub mod revocation_id_type;
 pub mod id_type;
 /// PublicMaid
 pub mod public_id_type;
+/// Rotation of an IdType signed off by its RevocationIdType
+pub mod key_rotation;
+/// Retirement of a PublicIdType signed off by its RevocationIdType
+pub mod revocation_certificate;
+/// Derivation of ids from a user's credentials
+pub mod credentials;
+/// Streaming encryption used by IdType::seal_stream
+pub mod stream;
+mod keystore;
+mod secret;
 
 pub use self::revocation_id_type::*;
 pub use self::id_type::*;
 pub use self::public_id_type::*;
+pub use self::key_rotation::*;
+pub use self::revocation_certificate::*;
+pub use self::credentials::*;
 #[test]
 fn dummy()  {
 }
@@ -17,9 +17,12 @@
 
 use cbor;
 use cbor::CborTagEncode;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use sodiumoxide::crypto;
 use helper::*;
+use signable::{Signable, SignableBytes};
 use routing::NameType;
 use routing::sendable::Sendable;
 use std::fmt;
@@ -43,6 +46,7 @@ pub struct PublicIdType {
     type_tag: u64,
     public_keys: (crypto::sign::PublicKey, crypto::box_::PublicKey),
     revocation_public_key: crypto::sign::PublicKey,
+    sequence_number: u64,
     signature: crypto::sign::Signature
 }
 
@@ -74,38 +78,69 @@ impl PartialEq for PublicIdType {
         slice_equal(&self.public_keys.0 .0, &other.public_keys.0 .0) &&
         slice_equal(&self.public_keys.1 .0, &other.public_keys.1 .0) &&
         slice_equal(&self.revocation_public_key.0, &other.revocation_public_key.0) &&
+        self.sequence_number == other.sequence_number &&
         slice_equal(&self.signature.0, &other.signature.0)
     }
 }
 
 impl fmt::Debug for PublicIdType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "PublicIdType {{ type_tag:{}, public_keys:({:?}, {:?}), revocation_public_key:{:?}, signature:{:?}}}",
+        write!(f, "PublicIdType {{ type_tag:{}, public_keys:({:?}, {:?}), revocation_public_key:{:?}, sequence_number:{}, signature:{:?}}}",
             self.type_tag, self.public_keys.0 .0.to_vec(), self.public_keys.1 .0.to_vec(), self.revocation_public_key.0.to_vec(),
-            self.signature.0.to_vec())
+            self.sequence_number, self.signature.0.to_vec())
+    }
+}
+
+/// Signs "PublicIdType", type tag, public signing key, public encryption key, revocation public
+/// key and sequence number
+impl Signable for PublicIdType {
+    fn signable_bytes(&self) -> Vec<u8> {
+        SignableBytes::new("PublicIdType").u64(self.type_tag)
+                                          .bytes(&self.public_keys.0 .0)
+                                          .bytes(&self.public_keys.1 .0)
+                                          .bytes(&self.revocation_public_key.0)
+                                          .u64(self.sequence_number)
+                                          .into_bytes()
     }
 }
 
 impl PublicIdType {
     /// An instanstance of the PublicIdType can be created using the new()
+    /// The sequence number of the returned instance is 0
     pub fn new(id_type: &IdType, revocation_id: &RevocationIdType) -> PublicIdType {
-        let type_tag = revocation_id.type_tags().2;
-        let public_keys = id_type.public_keys().clone();
-        let revocation_public_key = revocation_id.public_key();
-        let combined_iter = (public_keys.0).0.into_iter().chain((public_keys.1).0.into_iter().chain(revocation_public_key.0.into_iter()));
-        let mut combined: Vec<u8> = Vec::new();
-        for iter in combined_iter {
-            combined.push(*iter);
-        }
-        for i in type_tag.to_string().into_bytes().into_iter() {
-            combined.push(i);
-        }
-        let message_length = combined.len();
-        let signature = revocation_id.sign(&combined).into_iter().skip(message_length).collect::<Vec<_>>();
-        let signature_arr = convert_to_array!(signature, crypto::sign::SIGNATUREBYTES);
-        PublicIdType { type_tag: type_tag, public_keys: public_keys,
+        PublicIdType::new_with_sequence_number(id_type, revocation_id, 0)
+    }
+
+    /// Creates the PublicIdType of an IdType which replaces earlier ones; see `rotate_id_type`
+    pub fn new_with_sequence_number(id_type: &IdType, revocation_id: &RevocationIdType,
+                                    sequence_number: u64) -> PublicIdType {
+        let mut public_id = PublicIdType { type_tag: revocation_id.type_tags().2,
+             public_keys: id_type.public_keys().clone(),
              revocation_public_key: revocation_id.public_key().clone(),
-             signature: crypto::sign::Signature(signature_arr.unwrap()) }
+             sequence_number: sequence_number,
+             signature: crypto::sign::Signature([0u8; crypto::sign::SIGNATUREBYTES]) };
+        public_id.signature = revocation_id.sign_detached(&public_id.signable_bytes());
+        public_id
+    }
+
+    /// Returns true if the signature was made by the revocation key over the public keys, type
+    /// tag and sequence number
+    pub fn verify(&self) -> bool {
+        crypto::sign::verify_detached(&self.signature, &self.signable_bytes(), &self.revocation_public_key)
+    }
+
+    /// Decodes a cbor encoded PublicIdType, rejecting it if verify() fails.  Returns BadTypeTag if
+    /// bytes hold another of the types in `data_tags`.
+    pub fn decode_and_verify(bytes: &[u8]) -> Result<PublicIdType, ::Error> {
+        let public_id = match try!(::AnyData::from_bytes(bytes)) {
+            ::AnyData::PublicMaid(public_id) | ::AnyData::PublicMpid(public_id) => public_id,
+            _ => return Err(::Error::BadTypeTag),
+        };
+        if public_id.verify() {
+            Ok(public_id)
+        } else {
+            Err(::Error::SignatureVerification)
+        }
     }
     /// Returns the PublicKeys
     pub fn public_keys(&self) -> &(crypto::sign::PublicKey, crypto::box_::PublicKey) {
@@ -115,12 +150,37 @@ impl PublicIdType {
     pub fn revocation_public_key(&self) -> &crypto::sign::PublicKey {
         &self.revocation_public_key
     }
+    /// Returns the sequence number, incremented each time the IdType is rotated
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
     /// Returns the Signature of PublicIdType
     pub fn signature(&self) -> &crypto::sign::Signature {
         &self.signature
     }
+    /// Returns true if signature was made over data by the IdType this PublicIdType was created
+    /// from
+    pub fn verify_detached(&self, data: &[u8], signature: &crypto::sign::Signature) -> bool {
+        crypto::sign::verify_detached(signature, data, &self.public_keys.0)
+    }
+    /// Encrypts data so that only the IdType of this PublicIdType can read it, without revealing
+    /// the sender.  A new key pair is generated for every call and its public key is sent along
+    /// with the nonce: the result is ephemeral public key | nonce | ciphertext.  Use
+    /// `IdType::open_anonymous` to decrypt.
+    pub fn seal_anonymous(&self, data: &[u8]) -> Vec<u8> {
+        let (ephemeral_public_key, ephemeral_secret_key) = crypto::box_::gen_keypair();
+        let nonce = crypto::box_::gen_nonce();
+        let mut sealed = ephemeral_public_key.0.to_vec();
+        sealed.extend(nonce.0.iter().cloned());
+        sealed.extend(crypto::box_::seal(data, &nonce, &self.public_keys.1, &ephemeral_secret_key).into_iter());
+        sealed
+    }
 }
 
+// Number of fields in the encoding, and in that of format version 0 which has no sequence number
+const FIELD_COUNT: usize = 6;
+const LEGACY_FIELD_COUNT: usize = 5;
+
 impl Encodable for PublicIdType {
     fn encode<E: Encoder>(&self, e: &mut E)->Result<(), E::Error> {
         let (crypto::sign::PublicKey(ref pub_sign_vec), crypto::box_::PublicKey(pub_asym_vec)) = self.public_keys;
@@ -132,37 +192,70 @@ impl Encodable for PublicIdType {
             pub_sign_vec.as_ref(),
             pub_asym_vec.as_ref(),
             revocation_public_key_vec.as_ref(),
+            self.sequence_number,
             signature.as_ref())).encode(e)
     }
 }
 
+// Format version 0 had no sequence number; those public ids are read as the first of their chain.
+// What they hold in place of a signature is part of the signed message, so they fail verify().
 impl Decodable for PublicIdType {
     fn decode<D: Decoder>(d: &mut D)-> Result<PublicIdType, D::Error> {
-    let (tag_type_vec, pub_sign_vec, pub_asym_vec, revocation_public_key_vec, signature_vec): (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
-    let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
-    let pub_asym_arr = convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES);
-    let revocation_public_key_arr = convert_to_array!(revocation_public_key_vec, crypto::box_::PUBLICKEYBYTES);
-    let signature_arr = convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES);
-
-    if pub_sign_arr.is_none() || pub_asym_arr.is_none() || revocation_public_key_arr.is_none()
-        || signature_arr.is_none() {
-             return Err(d.error("Bad PublicIdType size"));
-    }
-
-    let type_tag: u64 = match String::from_utf8(tag_type_vec) {
-        Ok(string) =>  {
-            match string.parse::<u64>() {
-                Ok(type_tag) => type_tag,
-                Err(_) => return Err(d.error("Bad Tag Type"))
+        d.read_seq(|d, len| {
+            if len != LEGACY_FIELD_COUNT && len != FIELD_COUNT {
+                return Err(d.error("Bad PublicIdType size"));
             }
-        },
-        Err(_) => return Err(d.error("Bad Tag Type"))
-    };
+            let tag_type_vec: Vec<u8> = try!(d.read_seq_elt(0, Decodable::decode));
+            let pub_sign_vec: Vec<u8> = try!(d.read_seq_elt(1, Decodable::decode));
+            let pub_asym_vec: Vec<u8> = try!(d.read_seq_elt(2, Decodable::decode));
+            let revocation_public_key_vec: Vec<u8> = try!(d.read_seq_elt(3, Decodable::decode));
+            let sequence_number = if len == FIELD_COUNT { try!(d.read_seq_elt(4, Decodable::decode)) } else { 0 };
+            let signature_vec: Vec<u8> = try!(d.read_seq_elt(len - 1, Decodable::decode));
+            try_decode!(d, check_length(pub_sign_vec.len(), crypto::sign::PUBLICKEYBYTES));
+            try_decode!(d, check_length(pub_asym_vec.len(), crypto::box_::PUBLICKEYBYTES));
+            try_decode!(d, check_length(revocation_public_key_vec.len(), crypto::sign::PUBLICKEYBYTES));
+            try_decode!(d, check_length(signature_vec.len(), crypto::sign::SIGNATUREBYTES));
+            let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
+            let pub_asym_arr = convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES);
+            let revocation_public_key_arr = convert_to_array!(revocation_public_key_vec, crypto::sign::PUBLICKEYBYTES);
+            let signature_arr = convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES);
+
+            let type_tag = try_decode!(d, parse_type_tag(tag_type_vec));
+
+            Ok(PublicIdType{ type_tag: type_tag,
+                public_keys: (crypto::sign::PublicKey(pub_sign_arr.unwrap()), crypto::box_::PublicKey(pub_asym_arr.unwrap())),
+                revocation_public_key: crypto::sign::PublicKey(revocation_public_key_arr.unwrap()),
+                sequence_number: sequence_number,
+                signature: crypto::sign::Signature(signature_arr.unwrap())})
+        })
+    }
+}
+
+impl ToJson for PublicIdType {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(self.type_tag)
+            .bytes("public_sign_key", &self.public_keys.0 .0)
+            .bytes("public_encrypt_key", &self.public_keys.1 .0)
+            .bytes("revocation_public_key", &self.revocation_public_key.0)
+            .field("sequence_number", self.sequence_number)
+            .bytes("signature", &self.signature.0)
+            .build()
+    }
+}
 
-    Ok(PublicIdType{ type_tag: type_tag,
-        public_keys: (crypto::sign::PublicKey(pub_sign_arr.unwrap()), crypto::box_::PublicKey(pub_asym_arr.unwrap())),
-        revocation_public_key: crypto::sign::PublicKey(revocation_public_key_arr.unwrap()),
-        signature: crypto::sign::Signature(signature_arr.unwrap())})
+impl FromJson for PublicIdType {
+    fn from_json(json: &Json) -> Result<PublicIdType, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::PUBLIC_MAID_TAG, ::data_tags::PUBLIC_MPID_TAG]));
+        let pub_sign_vec = try!(object.fixed_bytes("public_sign_key", crypto::sign::PUBLICKEYBYTES));
+        let pub_asym_vec = try!(object.fixed_bytes("public_encrypt_key", crypto::box_::PUBLICKEYBYTES));
+        let revocation_public_key_vec = try!(object.fixed_bytes("revocation_public_key", crypto::sign::PUBLICKEYBYTES));
+        let signature_vec = try!(object.fixed_bytes("signature", crypto::sign::SIGNATUREBYTES));
+        Ok(PublicIdType{ type_tag: try!(object.u64("tag")),
+            public_keys: (crypto::sign::PublicKey(convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES).unwrap()),
+                          crypto::box_::PublicKey(convert_to_array!(pub_asym_vec, crypto::box_::PUBLICKEYBYTES).unwrap())),
+            revocation_public_key: crypto::sign::PublicKey(convert_to_array!(revocation_public_key_vec, crypto::sign::PUBLICKEYBYTES).unwrap()),
+            sequence_number: try!(object.u64("sequence_number")),
+            signature: crypto::sign::Signature(convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES).unwrap())})
     }
 }
 
@@ -170,12 +263,18 @@ impl Decodable for PublicIdType {
 mod test {
     use super::*;
     use cbor;
+    use cbor::CborTagEncode;
+    use json;
     use Random;
     use super::super::{ IdType, RevocationIdType };
     use MaidTypeTags;
     use MpidTypeTags;
     use sodiumoxide::crypto;
     use routing::types::array_as_vector;
+    use routing::sendable::Sendable;
+    use rustc_serialize::hex::ToHex;
+    use signable::Signable;
+    use signable::expected::{field, number};
 
     impl Random for PublicIdType {
         fn generate_random() -> PublicIdType {
@@ -201,7 +300,7 @@ mod test {
 
         let mut d = cbor::Decoder::from_bytes(e.as_bytes());
         match d.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::PubMaid(obj_after) => assert_eq!(obj_before, obj_after),
+            ::AnyData::PublicMaid(obj_after) => assert_eq!(obj_before, obj_after),
             _ => panic!("Unexpected!"),
         }
     }
@@ -234,12 +333,119 @@ mod test {
         for i in type_tag.to_string().into_bytes().into_iter() {
             combined.push(i);
         }
+        for i in 0u64.to_string().into_bytes().into_iter() {
+            combined.push(i);
+        }
 
-        let message_length = combined.len();
-        let signature = revocation_maid.sign(&combined).into_iter().skip(message_length).collect::<Vec<_>>();
+        let signature = revocation_maid.sign(&combined).into_iter().take(crypto::sign::SIGNATUREBYTES).collect::<Vec<_>>();
         let signature_array = convert_to_array!(signature, crypto::sign::SIGNATUREBYTES);
         let signature = crypto::sign::Signature(signature_array.unwrap());
 
         assert_eq!(array_as_vector(&signature.0), array_as_vector(&public_maid.signature().0));
     }
+
+    #[test]
+    fn verify() {
+        let public_maid = PublicIdType::generate_random();
+        assert!(public_maid.verify());
+
+        let mut wrong_type_tag = public_maid.clone();
+        wrong_type_tag.type_tag = ::data_tags::PUBLIC_MPID_TAG;
+        assert!(!wrong_type_tag.verify());
+
+        let mut wrong_keys = public_maid.clone();
+        wrong_keys.public_keys = PublicIdType::generate_random().public_keys;
+        assert!(!wrong_keys.verify());
+
+        let mut wrong_revocation_key = public_maid.clone();
+        wrong_revocation_key.revocation_public_key = PublicIdType::generate_random().revocation_public_key;
+        assert!(!wrong_revocation_key.verify());
+    }
+
+    #[test]
+    fn verify_detached() {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let maid = IdType::new(&revocation_maid);
+        let public_maid = PublicIdType::new(&maid, &revocation_maid);
+        let data = vec![1u8; 100];
+        let signature = maid.sign_detached(&data);
+        assert!(public_maid.verify_detached(&data, &signature));
+        assert!(!public_maid.verify_detached(&data[1..], &signature));
+        assert!(!public_maid.verify_detached(&data, &revocation_maid.sign_detached(&data)));
+    }
+
+    #[test]
+    fn signable_bytes() {
+        let public_maid = PublicIdType::new_with_sequence_number(&IdType::generate_random(),
+                                                                 &RevocationIdType::new::<MaidTypeTags>(), 300);
+        let mut expected = field(b"PublicIdType");
+        expected.extend(number(::data_tags::PUBLIC_MAID_TAG));
+        expected.extend(field(&public_maid.public_keys().0 .0));
+        expected.extend(field(&public_maid.public_keys().1 .0));
+        expected.extend(field(&public_maid.revocation_public_key().0));
+        expected.extend(number(300));
+        assert_eq!(public_maid.signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(public_maid.signature(), &expected,
+                                              public_maid.revocation_public_key()));
+
+        let fixed = PublicIdType { type_tag: ::data_tags::PUBLIC_MAID_TAG,
+                                   public_keys: (crypto::sign::PublicKey([1u8; 32]), crypto::box_::PublicKey([2u8; 32])),
+                                   revocation_public_key: crypto::sign::PublicKey([7u8; 32]),
+                                   sequence_number: 300,
+                                   signature: crypto::sign::Signature([8u8; 64]),
+                                 };
+        assert_eq!(fixed.signable_bytes().to_hex(),
+                   concat!("000000000000000c5075626c6963496454797065000000000053aa65000000000000002001010101",
+                           "01010101010101010101010101010101010101010101010101010101000000000000002002020202",
+                           "02020202020202020202020202020202020202020202020202020202000000000000002007070707",
+                           "07070707070707070707070707070707070707070707070707070707000000000000012c"));
+    }
+
+    #[test]
+    fn json_public_maid() {
+        let public_maid = PublicIdType::generate_random();
+        let decoded = json::from_str::<PublicIdType>(&json::to_string(&public_maid)).unwrap();
+        assert_eq!(decoded, public_maid);
+        assert!(decoded.verify());
+        assert_eq!(decoded.serialised_contents(), public_maid.serialised_contents());
+
+        let maid = IdType::generate_random();
+        assert!(json::from_str::<PublicIdType>(&maid.to_json_with_secret_keys().to_string()).is_err());
+    }
+
+    #[test]
+    fn decode_and_verify() {
+        let public_maid = PublicIdType::generate_random();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&public_maid]).unwrap();
+        assert_eq!(PublicIdType::decode_and_verify(e.as_bytes()).unwrap(), public_maid);
+
+        let mut forged = public_maid.clone();
+        forged.public_keys = PublicIdType::generate_random().public_keys;
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&forged]).unwrap();
+        match PublicIdType::decode_and_verify(e.as_bytes()) {
+            Err(::Error::SignatureVerification) => (),
+            _ => panic!("Unexpected!"),
+        }
+        // Decoding without verification still accepts it
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::PublicMaid(decoded)) => assert_eq!(decoded, forged),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Other known types are refused as the wrong type, unknown ones as unknown
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&IdType::generate_random().public_part()]).unwrap();
+        match PublicIdType::decode_and_verify(e.as_bytes()) {
+            Err(::Error::BadTypeTag) => (),
+            _ => panic!("Unexpected!"),
+        }
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(::MAIDSAFE_TAG, &0u64)]).unwrap();
+        match PublicIdType::decode_and_verify(e.as_bytes()) {
+            Err(::Error::UnknownTag(tag)) => assert_eq!(tag, ::MAIDSAFE_TAG),
+            _ => panic!("Unexpected!"),
+        }
+    }
 }
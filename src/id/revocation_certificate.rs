@@ -0,0 +1,391 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use cbor;
+use cbor::CborTagEncode;
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use sodiumoxide::crypto;
+use helper::*;
+use json::{FromJson, Object, ObjectBuilder};
+use rustc_serialize::json::{Json, ToJson};
+use signable::{Signable, SignableBytes};
+use routing::NameType;
+use routing::sendable::Sendable;
+use std::fmt;
+use super::revocation_id_type::*;
+use super::public_id_type::*;
+
+/// Reason for revoking a PublicIdType
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RevocationReason {
+    /// No reason given
+    Unspecified,
+    /// The secret keys of the IdType have been, or may have been, disclosed
+    KeyCompromise,
+    /// The IdType has been replaced by another
+    Superseded,
+    /// The identity is no longer in use
+    CessationOfUse,
+}
+
+impl RevocationReason {
+    /// Returns the code used to serialise the reason
+    pub fn code(&self) -> u64 {
+        match *self {
+            RevocationReason::Unspecified => 0,
+            RevocationReason::KeyCompromise => 1,
+            RevocationReason::Superseded => 2,
+            RevocationReason::CessationOfUse => 3,
+        }
+    }
+
+    /// Returns the reason with the given code
+    pub fn from_code(code: u64) -> Result<RevocationReason, ::Error> {
+        match code {
+            0 => Ok(RevocationReason::Unspecified),
+            1 => Ok(RevocationReason::KeyCompromise),
+            2 => Ok(RevocationReason::Superseded),
+            3 => Ok(RevocationReason::CessationOfUse),
+            _ => Err(::Error::InvalidReasonCode(code)),
+        }
+    }
+}
+
+/// RevocationCertificate
+///
+/// Signed by a RevocationIdType to retire one of its PublicIdTypes.  Vaults check the certificate
+/// with `verify` before removing the public id.
+///
+/// #Examples
+///
+/// ```
+/// use maidsafe_types::{IdType, RevocationIdType, MaidTypeTags, PublicIdType};
+/// use maidsafe_types::id::{RevocationCertificate, RevocationReason};
+///
+/// let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+/// let maid = IdType::new(&revocation_maid);
+/// let public_maid = PublicIdType::new(&maid, &revocation_maid);
+///
+/// let certificate = RevocationCertificate::new(&revocation_maid, &public_maid,
+///                                              RevocationReason::KeyCompromise, 1435000000).unwrap();
+/// assert!(certificate.verify(&public_maid).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct RevocationCertificate {
+    revoked_name: NameType,
+    reason: RevocationReason,
+    timestamp: u64,
+    revocation_public_key: crypto::sign::PublicKey,
+    signature: crypto::sign::Signature,
+}
+
+/// Signs "RevocationCertificate", revoked name, reason code, timestamp and revocation public key
+impl Signable for RevocationCertificate {
+    fn signable_bytes(&self) -> Vec<u8> {
+        SignableBytes::new("RevocationCertificate").bytes(&self.revoked_name.0).u64(self.reason.code())
+            .u64(self.timestamp).bytes(&self.revocation_public_key.0).into_bytes()
+    }
+}
+
+impl RevocationCertificate {
+    /// Creates a certificate revoking public_id, which must have been signed by revocation_id.
+    /// timestamp is the time of revocation in seconds since the Unix epoch.
+    pub fn new(revocation_id: &RevocationIdType, public_id: &PublicIdType, reason: RevocationReason,
+               timestamp: u64) -> Result<RevocationCertificate, ::Error> {
+        if !slice_equal(&public_id.revocation_public_key().0, &revocation_id.public_key().0) {
+            return Err(::Error::SignatureVerification);
+        }
+        let mut certificate = RevocationCertificate {
+            revoked_name: public_id.name(),
+            reason: reason,
+            timestamp: timestamp,
+            revocation_public_key: revocation_id.public_key().clone(),
+            signature: crypto::sign::Signature([0u8; crypto::sign::SIGNATUREBYTES]),
+        };
+        certificate.signature = revocation_id.sign_detached(&certificate.signable_bytes());
+        Ok(certificate)
+    }
+
+    /// Returns the name of the revoked PublicIdType
+    pub fn revoked_name(&self) -> &NameType {
+        &self.revoked_name
+    }
+    /// Returns the reason for the revocation
+    pub fn reason(&self) -> RevocationReason {
+        self.reason
+    }
+    /// Returns the time of revocation in seconds since the Unix epoch
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+    /// Returns the public key of the RevocationIdType which signed the certificate
+    pub fn revocation_public_key(&self) -> &crypto::sign::PublicKey {
+        &self.revocation_public_key
+    }
+    /// Returns the signature
+    pub fn signature(&self) -> &crypto::sign::Signature {
+        &self.signature
+    }
+
+    /// Checks that the certificate names public_id and was signed by its revocation key
+    pub fn verify(&self, public_id: &PublicIdType) -> Result<(), ::Error> {
+        if self.revoked_name != public_id.name() {
+            return Err(::Error::NameMismatch);
+        }
+        if !slice_equal(&self.revocation_public_key.0, &public_id.revocation_public_key().0) {
+            return Err(::Error::SignatureVerification);
+        }
+        if !crypto::sign::verify_detached(&self.signature, &self.signable_bytes(), &self.revocation_public_key) {
+            return Err(::Error::SignatureVerification);
+        }
+        Ok(())
+    }
+}
+
+impl Sendable for RevocationCertificate {
+    fn name(&self) -> NameType {
+        let mut combined = self.signable_bytes();
+        combined.extend(self.signature.0.iter().cloned());
+        NameType(crypto::hash::sha512::hash(&combined).0)
+    }
+
+    fn type_tag(&self) -> u64 {
+        ::data_tags::REVOCATION_CERTIFICATE_TAG
+    }
+
+    fn serialised_contents(&self) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&self]).unwrap();
+        e.into_bytes()
+    }
+
+    fn refresh(&self) -> bool {
+        false
+    }
+
+    fn merge(&self, _: Vec<Box<Sendable>>) -> Option<Box<Sendable>> { None }
+}
+
+impl PartialEq for RevocationCertificate {
+    fn eq(&self, other: &RevocationCertificate) -> bool {
+        self.revoked_name == other.revoked_name &&
+        self.reason == other.reason &&
+        self.timestamp == other.timestamp &&
+        slice_equal(&self.revocation_public_key.0, &other.revocation_public_key.0) &&
+        slice_equal(&self.signature.0, &other.signature.0)
+    }
+}
+
+impl fmt::Debug for RevocationCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RevocationCertificate {{ revoked_name:{:?}, reason:{:?}, timestamp:{}, revocation_public_key:{:?}, signature:{:?} }}",
+            self.revoked_name, self.reason, self.timestamp, self.revocation_public_key.0.to_vec(),
+            self.signature.0.to_vec())
+    }
+}
+
+impl Encodable for RevocationCertificate {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        CborTagEncode::new(::data_tags::REVOCATION_CERTIFICATE_TAG, &(
+            &self.revoked_name,
+            self.reason.code(),
+            self.timestamp,
+            self.revocation_public_key.0.as_ref(),
+            self.signature.0.as_ref())).encode(e)
+    }
+}
+
+impl Decodable for RevocationCertificate {
+    fn decode<D: Decoder>(d: &mut D) -> Result<RevocationCertificate, D::Error> {
+        let (revoked_name, reason_code, timestamp, revocation_public_key_vec, signature_vec):
+            (NameType, u64, u64, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        let reason = try_decode!(d, RevocationReason::from_code(reason_code));
+        try_decode!(d, check_length(revocation_public_key_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        try_decode!(d, check_length(signature_vec.len(), crypto::sign::SIGNATUREBYTES));
+        let revocation_public_key_arr = convert_to_array!(revocation_public_key_vec, crypto::sign::PUBLICKEYBYTES);
+        let signature_arr = convert_to_array!(signature_vec, crypto::sign::SIGNATUREBYTES);
+
+        Ok(RevocationCertificate {
+            revoked_name: revoked_name,
+            reason: reason,
+            timestamp: timestamp,
+            revocation_public_key: crypto::sign::PublicKey(revocation_public_key_arr.unwrap()),
+            signature: crypto::sign::Signature(signature_arr.unwrap()),
+        })
+    }
+}
+
+impl ToJson for RevocationCertificate {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::REVOCATION_CERTIFICATE_TAG)
+            .bytes("revoked_name", &self.revoked_name.0)
+            .field("reason", self.reason.code())
+            .field("timestamp", self.timestamp)
+            .bytes("revocation_public_key", &self.revocation_public_key.0)
+            .bytes("signature", &self.signature.0)
+            .build()
+    }
+}
+
+impl FromJson for RevocationCertificate {
+    fn from_json(json: &Json) -> Result<RevocationCertificate, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::REVOCATION_CERTIFICATE_TAG]));
+        Ok(RevocationCertificate {
+            revoked_name: try!(object.name_type("revoked_name")),
+            reason: try!(RevocationReason::from_code(try!(object.u64("reason")))),
+            timestamp: try!(object.u64("timestamp")),
+            revocation_public_key: try!(object.public_key("revocation_public_key")),
+            signature: try!(object.signature("signature")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cbor;
+    use cbor::CborTagEncode;
+    use json;
+    use routing::sendable::Sendable;
+    use super::super::{IdType, RevocationIdType, PublicIdType};
+    use MaidTypeTags;
+    use Random;
+    use routing::NameType;
+    use rustc_serialize::hex::ToHex;
+    use signable::Signable;
+    use signable::expected::{field, number};
+    use sodiumoxide::crypto;
+
+    #[test]
+    fn reason_codes() {
+        for reason in [RevocationReason::Unspecified, RevocationReason::KeyCompromise,
+                       RevocationReason::Superseded, RevocationReason::CessationOfUse].iter() {
+            assert_eq!(RevocationReason::from_code(reason.code()).unwrap(), *reason);
+        }
+        match RevocationReason::from_code(4) {
+            Err(::Error::InvalidReasonCode(code)) => assert_eq!(code, 4),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let public_maid = PublicIdType::new(&IdType::new(&revocation_maid), &revocation_maid);
+        let certificate = RevocationCertificate::new(&revocation_maid, &public_maid,
+                                                     RevocationReason::Superseded, 1435000000).unwrap();
+        assert_eq!(certificate.revoked_name(), &public_maid.name());
+        assert_eq!(certificate.reason(), RevocationReason::Superseded);
+        assert_eq!(certificate.timestamp(), 1435000000);
+        assert!(certificate.verify(&public_maid).is_ok());
+
+        // Certificate for another public id
+        let other_public_maid = PublicIdType::new(&IdType::new(&revocation_maid), &revocation_maid);
+        match certificate.verify(&other_public_maid) {
+            Err(::Error::NameMismatch) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Altered after signing
+        let mut altered = certificate.clone();
+        altered.reason = RevocationReason::KeyCompromise;
+        match altered.verify(&public_maid) {
+            Err(::Error::SignatureVerification) => (),
+            _ => panic!("Unexpected!"),
+        }
+        let mut altered = certificate.clone();
+        altered.timestamp += 1;
+        assert!(altered.verify(&public_maid).is_err());
+
+        // Only the revocation id of the public id may revoke it
+        let other_revocation_maid = RevocationIdType::generate_random();
+        assert!(RevocationCertificate::new(&other_revocation_maid, &public_maid,
+                                           RevocationReason::Unspecified, 1435000000).is_err());
+    }
+
+    #[test]
+    fn signable_bytes() {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let public_maid = PublicIdType::new(&IdType::new(&revocation_maid), &revocation_maid);
+        let certificate = RevocationCertificate::new(&revocation_maid, &public_maid,
+                                                     RevocationReason::KeyCompromise, 1435000000).unwrap();
+        let mut expected = field(b"RevocationCertificate");
+        expected.extend(field(&public_maid.name().0));
+        expected.extend(number(1));
+        expected.extend(number(1435000000));
+        expected.extend(field(&revocation_maid.public_key().0));
+        assert_eq!(certificate.signable_bytes(), expected);
+        assert!(crypto::sign::verify_detached(certificate.signature(), &expected,
+                                              revocation_maid.public_key()));
+
+        let fixed = RevocationCertificate { revoked_name: NameType([3u8; 64]),
+                                            reason: RevocationReason::KeyCompromise,
+                                            timestamp: 1435000000,
+                                            revocation_public_key: crypto::sign::PublicKey([7u8; 32]),
+                                            signature: crypto::sign::Signature([8u8; 64]),
+                                          };
+        assert_eq!(fixed.signable_bytes().to_hex(),
+                   concat!("00000000000000155265766f636174696f6e43657274696669636174650000000000000040030303",
+                           "03030303030303030303030303030303030303030303030303030303030303030303030303030303",
+                           "03030303030303030303030303030303030303030300000000000000010000000055885cc0000000",
+                           "00000000200707070707070707070707070707070707070707070707070707070707070707"));
+    }
+
+    #[test]
+    fn serialisation_revocation_certificate() {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let public_maid = PublicIdType::new(&IdType::new(&revocation_maid), &revocation_maid);
+        let certificate = RevocationCertificate::new(&revocation_maid, &public_maid,
+                                                     RevocationReason::CessationOfUse, 1435000000).unwrap();
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&certificate]).unwrap();
+
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Ok(::AnyData::RevocationCertificate(decoded)) => {
+                assert_eq!(decoded, certificate);
+                assert!(decoded.verify(&public_maid).is_ok());
+            },
+            _ => panic!("Unexpected!"),
+        }
+
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&CborTagEncode::new(::data_tags::REVOCATION_CERTIFICATE_TAG, &(
+            certificate.revoked_name(), 9u64, certificate.timestamp(),
+            certificate.revocation_public_key().0.as_ref(), certificate.signature().0.as_ref()))]).unwrap();
+        match ::AnyData::from_bytes(e.as_bytes()) {
+            Err(::Error::InvalidReasonCode(code)) => assert_eq!(code, 9),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn json_revocation_certificate() {
+        let revocation_maid = RevocationIdType::new::<MaidTypeTags>();
+        let public_maid = PublicIdType::new(&IdType::new(&revocation_maid), &revocation_maid);
+        let certificate = RevocationCertificate::new(&revocation_maid, &public_maid,
+                                                     RevocationReason::KeyCompromise, 1435000000).unwrap();
+        let json = json::to_string(&certificate);
+        let obj_after = json::from_str::<RevocationCertificate>(&json).unwrap();
+        assert_eq!(obj_after, certificate);
+        assert!(obj_after.verify(&public_maid).is_ok());
+
+        match json::from_str::<RevocationCertificate>(&json.replace("\"reason\":1", "\"reason\":9")) {
+            Err(::Error::InvalidReasonCode(code)) => assert_eq!(code, 9),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
@@ -15,20 +15,27 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+use cbor;
 use cbor::CborTagEncode;
+use json::{FromJson, Object, ObjectBuilder};
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_serialize::json::{Json, ToJson};
 use sodiumoxide::crypto;
 use helper::*;
 use std::fmt;
 use IdTypeTags;
 use std::mem;
 use routing::NameType;
+use super::secret::Secret;
 
 /// The following key types use the internal cbor tag to identify them and this
 /// should be carried through to any json representation if stored on disk
 ///
 /// RevocationIdType
 ///
+/// The secret key never leaves the RevocationIdType except through `serialise_with_secret_key` or
+/// `to_json_with_secret_key`, and is zeroed in memory when it is dropped.
+///
 /// #Examples
 /// ```
 /// extern crate sodiumoxide;
@@ -38,11 +45,10 @@ use routing::NameType;
 /// let an_maid : maidsafe_types::RevocationIdType = maidsafe_types::RevocationIdType::new::<maidsafe_types::MaidTypeTags>();
 /// ```
 ///
-#[derive(Clone)]
 pub struct RevocationIdType {
     type_tags: (u64, u64, u64),  // type tags for revocation, id and public ids
     public_key: crypto::sign::PublicKey,
-    secret_key: crypto::sign::SecretKey
+    secret_key: Secret<crypto::sign::SecretKey>
 }
 
 impl PartialEq for RevocationIdType {
@@ -70,7 +76,19 @@ impl RevocationIdType {
         RevocationIdType {
             type_tags: (type_tags.revocation_id_type_tag(), type_tags.id_type_tag(), type_tags.public_id_type_tag()),
             public_key: pub_sign_key,
-            secret_key: sec_sign_key
+            secret_key: Secret::new(sec_sign_key)
+        }
+    }
+
+    /// Creates a RevocationIdType whose keys are derived from seed.  The same seed always gives
+    /// the same RevocationIdType, so it can be regenerated rather than stored.
+    pub fn from_seed<TypeTags>(seed: &crypto::sign::Seed) -> RevocationIdType where TypeTags: IdTypeTags {
+        let (pub_sign_key, sec_sign_key) = crypto::sign::keypair_from_seed(seed);
+        let type_tags: TypeTags = unsafe { mem::uninitialized() };
+        RevocationIdType {
+            type_tags: (type_tags.revocation_id_type_tag(), type_tags.id_type_tag(), type_tags.public_id_type_tag()),
+            public_key: pub_sign_key,
+            secret_key: Secret::new(sec_sign_key)
         }
     }
 
@@ -87,6 +105,11 @@ impl RevocationIdType {
         NameType(crypto::hash::sha512::hash(&combined).0)
     }
 
+    /// Returns a copy of the RevocationIdType holding only its public key
+    pub fn public_part(&self) -> RevocationIdTypePublicPart {
+        RevocationIdTypePublicPart { type_tags: self.type_tags, public_key: self.public_key }
+    }
+
     /// Returns type tags
     pub fn type_tags(&self) -> &(u64, u64, u64) {
         &self.type_tags
@@ -96,63 +119,196 @@ impl RevocationIdType {
     pub fn type_tag(&self) -> &u64 {
         &self.type_tags.0
     }
-    /// Returns the SecretKey of the RevocationIdType
-    pub fn secret_key(&self) -> &crypto::sign::SecretKey {
-        &self.secret_key
-    }
     /// Returns the PublicKey of the AnMaid
     pub fn public_key(&self) -> &crypto::sign::PublicKey {
         &self.public_key
     }
     /// Signs the data with the SecretKey of the AnMaid and recturns the Signed Data
     pub fn sign(&self, data : &[u8]) -> Vec<u8> {
-        return crypto::sign::sign(&data, &self.secret_key)
+        return crypto::sign::sign(&data, self.secret_key.get())
+    }
+    /// Signs the data with the SecretKey of the AnMaid and returns only the Signature
+    pub fn sign_detached(&self, data: &[u8]) -> crypto::sign::Signature {
+        crypto::sign::sign_detached(data, self.secret_key.get())
+    }
+    /// Returns true if signature was made over data by the AnMaid
+    pub fn verify_detached(&self, data: &[u8], signature: &crypto::sign::Signature) -> bool {
+        crypto::sign::verify_detached(signature, data, &self.public_key)
+    }
+    /// Encodes the RevocationIdType, including its secret key, and encrypts it under a key
+    /// derived from password.  The result can be safely written to disk.
+    pub fn export_encrypted(&self, password: &[u8]) -> Result<Vec<u8>, ::Error> {
+        super::keystore::seal(&self.serialise_with_secret_key(), password)
+    }
+    /// Cbor encodes the RevocationIdType including its secret key.  RevocationIdType deliberately
+    /// does not implement Encodable; use `public_part` where the secret key is not needed.
+    pub fn serialise_with_secret_key(&self) -> Vec<u8> {
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&WithSecretKey(self)]).unwrap();
+        e.into_bytes()
+    }
+    /// Returns the JSON representation of the RevocationIdType including its secret key.
+    /// RevocationIdType deliberately does not implement ToJson.
+    pub fn to_json_with_secret_key(&self) -> Json {
+        ObjectBuilder::new(self.type_tags.0)
+            .field("type_tags", vec![self.type_tags.0, self.type_tags.1, self.type_tags.2])
+            .bytes("public_key", &self.public_key.0)
+            .bytes("secret_key", &self.secret_key.get().0)
+            .build()
+    }
+    /// Reads a RevocationIdType from the output of `to_json_with_secret_key`
+    pub fn from_json_with_secret_key(json: &Json) -> Result<RevocationIdType, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::AN_MAID_TAG, ::data_tags::AN_MPID_TAG]));
+        let type_tags = try!(object.array("type_tags")).iter().filter_map(|tag| tag.as_u64()).collect::<Vec<_>>();
+        if type_tags.len() != 3 || type_tags[0] != try!(object.u64("tag")) {
+            return Err(::Error::BadTypeTag);
+        }
+        let pub_sign_vec = try!(object.fixed_bytes("public_key", crypto::sign::PUBLICKEYBYTES));
+        let sec_sign_vec = try!(object.fixed_bytes("secret_key", crypto::sign::SECRETKEYBYTES));
+        Ok(RevocationIdType{ type_tags: (type_tags[0], type_tags[1], type_tags[2]),
+             public_key: crypto::sign::PublicKey(convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES).unwrap()),
+             secret_key: Secret::new(crypto::sign::SecretKey(convert_to_array!(sec_sign_vec, crypto::sign::SECRETKEYBYTES).unwrap())) })
+    }
+    /// Recovers a RevocationIdType from the output of `export_encrypted`
+    pub fn import_encrypted(keystore: &[u8], password: &[u8]) -> Result<RevocationIdType, ::Error> {
+        match try!(::AnyData::from_bytes(&try!(super::keystore::open(keystore, password)))) {
+            ::AnyData::AnMaid(revocation_id) | ::AnyData::AnMpid(revocation_id) => Ok(revocation_id),
+            _ => Err(::Error::BadTypeTag),
+        }
     }
 }
 
-fn convert_to_u64(num_vec: Vec<u8>) -> Option<u64> {
-    match String::from_utf8(num_vec) {
-         Ok(string) =>  {
-             match string.parse::<u64>() {
-                 Ok(type_tag) => Some(type_tag),
-                 Err(_) => None
-             }
-         },
-         Err(_) => None
-     }
-}
+// Only reachable through RevocationIdType::serialise_with_secret_key
+struct WithSecretKey<'a>(&'a RevocationIdType);
 
-impl Encodable for RevocationIdType {
+impl<'a> Encodable for WithSecretKey<'a> {
     fn encode<E: Encoder>(&self, e: &mut E)->Result<(), E::Error> {
-        let revocation_type_tag_vec = self.type_tags.0.to_string().into_bytes();
-        let id_type_tag_vec = self.type_tags.1.to_string().into_bytes();
-        let public_id_type_tag_vec = self.type_tags.2.to_string().into_bytes();
-        CborTagEncode::new(*self.type_tag(),
+        let revocation_type_tag_vec = self.0.type_tags.0.to_string().into_bytes();
+        let id_type_tag_vec = self.0.type_tags.1.to_string().into_bytes();
+        let public_id_type_tag_vec = self.0.type_tags.2.to_string().into_bytes();
+        CborTagEncode::new(*self.0.type_tag(),
              &(revocation_type_tag_vec,
                id_type_tag_vec,
                public_id_type_tag_vec,
-               self.public_key.0.as_ref(), self.secret_key.0.as_ref())).encode(e)
+               self.0.public_key.0.as_ref(), self.0.secret_key.get().0.as_ref())).encode(e)
     }
 }
 
 impl Decodable for RevocationIdType {
     fn decode<D: Decoder>(d: &mut D)-> Result<RevocationIdType, D::Error> {
         let(revocation_type_tag_vec, id_type_tag_vec, public_id_type_tag_vec , pub_sign_vec, sec_sign_vec) : (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        try_decode!(d, check_length(pub_sign_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        try_decode!(d, check_length(sec_sign_vec.len(), crypto::sign::SECRETKEYBYTES));
         let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
         let sec_sign_arr = convert_to_array!(sec_sign_vec, crypto::sign::SECRETKEYBYTES);
-        let (revocation_type_tag, id_type_tag, public_id_type_tag) = (
-            convert_to_u64(revocation_type_tag_vec),
-            convert_to_u64(id_type_tag_vec),
-            convert_to_u64(public_id_type_tag_vec));
-
-        if pub_sign_arr.is_none() || sec_sign_arr.is_none() || revocation_type_tag.is_none() ||
-            id_type_tag.is_none() ||  public_id_type_tag.is_none() {
-            return Err(d.error("Bad RevocationIdType size"));
-        }
+        let revocation_type_tag = try_decode!(d, parse_type_tag(revocation_type_tag_vec));
+        let id_type_tag = try_decode!(d, parse_type_tag(id_type_tag_vec));
+        let public_id_type_tag = try_decode!(d, parse_type_tag(public_id_type_tag_vec));
 
-        Ok(RevocationIdType{ type_tags: (revocation_type_tag.unwrap(), id_type_tag.unwrap(), public_id_type_tag.unwrap()),
+        Ok(RevocationIdType{ type_tags: (revocation_type_tag, id_type_tag, public_id_type_tag),
              public_key: crypto::sign::PublicKey(pub_sign_arr.unwrap()),
-             secret_key: crypto::sign::SecretKey(sec_sign_arr.unwrap()) })
+             secret_key: Secret::new(crypto::sign::SecretKey(sec_sign_arr.unwrap())) })
+    }
+}
+
+/// RevocationIdTypePublicPart
+///
+/// The public key of a RevocationIdType, safe to encode wherever the RevocationIdType needs to be
+/// referred to.
+///
+/// #Examples
+/// ```
+/// use maidsafe_types::{RevocationIdType, MaidTypeTags};
+///
+/// let an_maid = RevocationIdType::new::<MaidTypeTags>();
+/// let public_part = an_maid.public_part();
+/// assert_eq!(public_part.name(), an_maid.name());
+/// ```
+#[derive(Clone)]
+pub struct RevocationIdTypePublicPart {
+    type_tags: (u64, u64, u64),
+    public_key: crypto::sign::PublicKey,
+}
+
+impl RevocationIdTypePublicPart {
+    /// Returns the name of the RevocationIdType
+    pub fn name(&self) -> NameType {
+        let mut combined = self.public_key.0.to_vec();
+        combined.extend(self.type_tags.0.to_string().into_bytes().into_iter());
+        NameType(crypto::hash::sha512::hash(&combined).0)
+    }
+    /// Returns the type tags of the RevocationIdType
+    pub fn type_tags(&self) -> &(u64, u64, u64) {
+        &self.type_tags
+    }
+    /// Returns the PublicKey
+    pub fn public_key(&self) -> &crypto::sign::PublicKey {
+        &self.public_key
+    }
+    /// Returns true if signature was made over data by the RevocationIdType
+    pub fn verify_detached(&self, data: &[u8], signature: &crypto::sign::Signature) -> bool {
+        crypto::sign::verify_detached(signature, data, &self.public_key)
+    }
+}
+
+impl PartialEq for RevocationIdTypePublicPart {
+    fn eq(&self, other: &RevocationIdTypePublicPart) -> bool {
+        &self.type_tags == &other.type_tags &&
+        slice_equal(&self.public_key.0, &other.public_key.0)
+    }
+}
+
+impl fmt::Debug for RevocationIdTypePublicPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RevocationIdTypePublicPart( type_tags:{:?}, public_key: {:?} )", self.type_tags, self.public_key.0)
+    }
+}
+
+impl Encodable for RevocationIdTypePublicPart {
+    fn encode<E: Encoder>(&self, e: &mut E)->Result<(), E::Error> {
+        let revocation_type_tag_vec = self.type_tags.0.to_string().into_bytes();
+        let id_type_tag_vec = self.type_tags.1.to_string().into_bytes();
+        let public_id_type_tag_vec = self.type_tags.2.to_string().into_bytes();
+        CborTagEncode::new(::data_tags::REVOCATION_ID_PUBLIC_PART_TAG,
+             &(revocation_type_tag_vec,
+               id_type_tag_vec,
+               public_id_type_tag_vec,
+               self.public_key.0.as_ref())).encode(e)
+    }
+}
+
+impl Decodable for RevocationIdTypePublicPart {
+    fn decode<D: Decoder>(d: &mut D)-> Result<RevocationIdTypePublicPart, D::Error> {
+        let (revocation_type_tag_vec, id_type_tag_vec, public_id_type_tag_vec, pub_sign_vec): (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) = try!(Decodable::decode(d));
+        try_decode!(d, check_length(pub_sign_vec.len(), crypto::sign::PUBLICKEYBYTES));
+        let pub_sign_arr = convert_to_array!(pub_sign_vec, crypto::sign::PUBLICKEYBYTES);
+        let revocation_type_tag = try_decode!(d, parse_type_tag(revocation_type_tag_vec));
+        let id_type_tag = try_decode!(d, parse_type_tag(id_type_tag_vec));
+        let public_id_type_tag = try_decode!(d, parse_type_tag(public_id_type_tag_vec));
+
+        Ok(RevocationIdTypePublicPart{ type_tags: (revocation_type_tag, id_type_tag, public_id_type_tag),
+             public_key: crypto::sign::PublicKey(pub_sign_arr.unwrap()) })
+    }
+}
+
+impl ToJson for RevocationIdTypePublicPart {
+    fn to_json(&self) -> Json {
+        ObjectBuilder::new(::data_tags::REVOCATION_ID_PUBLIC_PART_TAG)
+            .field("type_tags", vec![self.type_tags.0, self.type_tags.1, self.type_tags.2])
+            .bytes("public_key", &self.public_key.0)
+            .build()
+    }
+}
+
+impl FromJson for RevocationIdTypePublicPart {
+    fn from_json(json: &Json) -> Result<RevocationIdTypePublicPart, ::Error> {
+        let object = try!(Object::with_tag(json, &[::data_tags::REVOCATION_ID_PUBLIC_PART_TAG]));
+        let type_tags = try!(object.array("type_tags")).iter().filter_map(|tag| tag.as_u64()).collect::<Vec<_>>();
+        if type_tags.len() != 3 {
+            return Err(::Error::BadTypeTag);
+        }
+        Ok(RevocationIdTypePublicPart{ type_tags: (type_tags[0], type_tags[1], type_tags[2]),
+             public_key: try!(object.public_key("public_key")) })
     }
 }
 
@@ -162,9 +318,10 @@ mod test {
 
     use self::rand::Rng;
     use cbor;
+    use json;
     use Random;
     use sodiumoxide::crypto;
-    use super::RevocationIdType;
+    use super::{RevocationIdType, RevocationIdTypePublicPart};
     use MaidTypeTags;
     use MpidTypeTags;
 
@@ -174,6 +331,16 @@ mod test {
         }
     }
 
+    // RevocationIdType is not Clone; a cbor round trip gives an independent copy for comparisons.
+    fn decoded_copy(revocation_id: &RevocationIdType) -> RevocationIdType {
+        let serialised = revocation_id.serialise_with_secret_key();
+        let mut d = cbor::Decoder::from_bytes(&serialised[..]);
+        match d.decode().next().unwrap().unwrap() {
+            ::AnyData::AnMaid(copy) | ::AnyData::AnMpid(copy) => copy,
+            _ => panic!("Unexpected!"),
+        }
+    }
+
 #[test]
     fn create_an_mpid() {
         let _ = RevocationIdType::new::<MpidTypeTags>();
@@ -182,34 +349,118 @@ mod test {
 #[test]
     fn serialisation_an_maid() {
         let obj_before = RevocationIdType::generate_random();
-        let mut e = cbor::Encoder::from_memory();
-        e.encode(&[&obj_before]).unwrap();
+        let serialised = obj_before.serialise_with_secret_key();
 
-        let mut d = cbor::Decoder::from_bytes(e.as_bytes());
+        let mut d = cbor::Decoder::from_bytes(&serialised[..]);
         match d.decode().next().unwrap().unwrap() {
-            ::test_utils::Parser::AnMaid(obj_after) => assert_eq!(obj_before, obj_after),
+            ::AnyData::AnMaid(obj_after) => assert_eq!(obj_before, obj_after),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+#[test]
+    fn json_an_maid() {
+        let obj_before = RevocationIdType::new::<MpidTypeTags>();
+        let json = obj_before.to_json_with_secret_key().to_string();
+        let obj_after = RevocationIdType::from_json_with_secret_key(&json::parse(&json).unwrap()).unwrap();
+        assert_eq!(obj_before, obj_after);
+        assert_eq!(obj_before.serialise_with_secret_key(), obj_after.serialise_with_secret_key());
+
+        let public_id = ::PublicIdType::generate_random();
+        assert!(RevocationIdType::from_json_with_secret_key(&json::parse(&json::to_string(&public_id)).unwrap()).is_err());
+    }
+
+#[test]
+    fn serialisation_public_part() {
+        let an_maid = RevocationIdType::generate_random();
+        let public_part = an_maid.public_part();
+        assert_eq!(public_part.name(), an_maid.name());
+        assert_eq!(public_part.type_tags(), an_maid.type_tags());
+
+        let mut e = cbor::Encoder::from_memory();
+        e.encode(&[&public_part]).unwrap();
+        let encoded = e.into_bytes();
+
+        // None of the secret key bytes may appear in the encoding
+        let secret_key = an_maid.secret_key.get().0.to_vec();
+        assert!(!encoded.windows(secret_key.len()).any(|window| window == &secret_key[..]));
+
+        match ::AnyData::from_bytes(&encoded) {
+            Ok(::AnyData::RevocationIdTypePublicPart(decoded)) => assert_eq!(decoded, public_part),
             _ => panic!("Unexpected!"),
         }
+
+        let obj_after = json::from_str::<RevocationIdTypePublicPart>(&json::to_string(&public_part)).unwrap();
+        assert_eq!(obj_after, public_part);
+        assert!(json::from_str::<RevocationIdTypePublicPart>(&an_maid.to_json_with_secret_key().to_string()).is_err());
+
+        let signature = an_maid.sign_detached(b"data");
+        assert!(public_part.verify_detached(b"data", &signature));
+        assert!(!public_part.verify_detached(b"date", &signature));
     }
 
 #[test]
     fn equality_assertion_an_maid() {
         let first_obj = RevocationIdType::generate_random();
         let second_obj = RevocationIdType::generate_random();
-        let cloned_obj = second_obj.clone();
+        let copied_obj = decoded_copy(&second_obj);
 
         assert!(first_obj != second_obj);
-        assert!(second_obj == cloned_obj);
+        assert!(second_obj == copied_obj);
+    }
+
+#[test]
+    fn from_seed() {
+        let seed = crypto::sign::Seed([7u8; crypto::sign::SEEDBYTES]);
+        let an_maid1 = RevocationIdType::from_seed::<MaidTypeTags>(&seed);
+        let an_maid2 = RevocationIdType::from_seed::<MaidTypeTags>(&seed);
+        assert_eq!(an_maid1, an_maid2);
+        assert!(an_maid1 != RevocationIdType::from_seed::<MpidTypeTags>(&seed));
+        assert!(an_maid1 != RevocationIdType::from_seed::<MaidTypeTags>(&crypto::sign::Seed([8u8; crypto::sign::SEEDBYTES])));
+
+        let signed = an_maid1.sign(&[1u8, 2, 3]);
+        assert!(crypto::sign::verify(&signed, &an_maid2.public_key()).is_some());
+    }
+
+#[test]
+    fn export_and_import_encrypted() {
+        let an_mpid = RevocationIdType::new::<MpidTypeTags>();
+        let keystore = an_mpid.export_encrypted(b"password").unwrap();
+        let imported = RevocationIdType::import_encrypted(&keystore, b"password").unwrap();
+        assert_eq!(imported, an_mpid);
+        assert_eq!(imported.secret_key.get().0.to_vec(), an_mpid.secret_key.get().0.to_vec());
+
+        match RevocationIdType::import_encrypted(&keystore, b"") {
+            Err(::Error::WrongPassword) => (),
+            _ => panic!("Unexpected!"),
+        }
+        let mut corrupted = keystore.clone();
+        corrupted[40] ^= 0xff;
+        match RevocationIdType::import_encrypted(&corrupted, b"password") {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+#[test]
+    fn sign_detached() {
+        let an_maid = RevocationIdType::generate_random();
+        let data = vec![1u8; 100];
+        let signature = an_maid.sign_detached(&data);
+        assert_eq!(signature.0.to_vec(), an_maid.sign(&data)[..crypto::sign::SIGNATUREBYTES].to_vec());
+        assert!(an_maid.verify_detached(&data, &signature));
+        assert!(!an_maid.verify_detached(&data[1..], &signature));
+        assert!(!RevocationIdType::generate_random().verify_detached(&data, &signature));
     }
 
 #[test]
     fn generation() {
         let maid1 = RevocationIdType::generate_random();
         let maid2 = RevocationIdType::generate_random();
-        let maid2_clone = maid2.clone();
+        let maid2_copy = decoded_copy(&maid2);
 
-        assert_eq!(maid2, maid2_clone);
-        assert!(!(maid2 != maid2_clone));
+        assert_eq!(maid2, maid2_copy);
+        assert!(!(maid2 != maid2_copy));
         assert!(maid1 != maid2);
 
         let random_bytes = rand::thread_rng().gen_iter::<u8>().take(100).collect::<Vec<u8>>();
@@ -0,0 +1,69 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::mem;
+use std::ptr;
+
+/// Holds secret key material on the heap and overwrites it with zeros when dropped.
+///
+/// Keeping the value boxed means moving the owning IdType moves only a pointer, so no stray copies
+/// of the key bytes are left behind on the stack.  Secret is deliberately not Clone: each key is
+/// held once and wiped once.  T is Copy, so it owns no pointers or destructor which zeroing would
+/// break; sodiumoxide's secret keys are plain key bytes.
+pub struct Secret<T: Copy> {
+    value: Box<T>,
+}
+
+impl<T: Copy> Secret<T> {
+    /// Moves value onto the heap
+    pub fn new(value: T) -> Secret<T> {
+        Secret { value: Box::new(value) }
+    }
+
+    /// Returns a reference to the secret value
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    fn wipe(&mut self) {
+        unsafe {
+            let bytes = &mut *self.value as *mut T as *mut u8;
+            for i in 0..mem::size_of::<T>() {
+                ptr::write_volatile(bytes.offset(i as isize), 0);
+            }
+        }
+    }
+}
+
+impl<T: Copy> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wipe() {
+        let mut secret = Secret::new([7u8; 64]);
+        assert!(secret.get().iter().all(|byte| *byte == 7));
+        secret.wipe();
+        assert!(secret.get().iter().all(|byte| *byte == 0));
+    }
+}
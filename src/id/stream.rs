@@ -0,0 +1,217 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Authenticated encryption of streams.
+//!
+//! A sealed stream is a random nonce prefix followed by frames:
+//!
+//! ```text
+//! prefix | length | ciphertext | length | ciphertext | ...
+//! ```
+//!
+//! `length` is the big-endian u32 length of the following ciphertext.  Each ciphertext holds a
+//! flag byte, set only in the last frame, followed by up to `FRAME_SIZE` bytes of data.  The nonce
+//! of a frame is the prefix followed by the big-endian frame number, so frames which are moved fail
+//! to authenticate, and a stream which ends before a frame with the flag set has been truncated.
+
+use sodiumoxide::crypto;
+use std::io::{self, Read, Write};
+
+/// Number of bytes of data held in each frame but the last
+pub const FRAME_SIZE: usize = 64 * 1024;
+
+const PREFIX_BYTES: usize = crypto::box_::NONCEBYTES - 8;
+const LENGTH_BYTES: usize = 4;
+const MORE_FRAMES: u8 = 0;
+const FINAL_FRAME: u8 = 1;
+
+// Reads until buffer is full or the reader is exhausted, returning the number of bytes read
+fn read_fully<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(count) => filled += count,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => (),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+fn frame_nonce(prefix: &[u8], frame_number: u64) -> crypto::box_::Nonce {
+    let mut nonce = crypto::box_::Nonce([0u8; crypto::box_::NONCEBYTES]);
+    for (dst, src) in nonce.0.iter_mut().zip(prefix.iter()) {
+        *dst = *src;
+    }
+    for i in 0..8 {
+        nonce.0[PREFIX_BYTES + i] = (frame_number >> (56 - 8 * i)) as u8;
+    }
+    nonce
+}
+
+/// Reads reader to the end, writing the sealed stream to writer
+pub fn seal<R: Read, W: Write>(key: &crypto::box_::PrecomputedKey, reader: &mut R, writer: &mut W)
+        -> Result<(), ::Error> {
+    let prefix = crypto::box_::gen_nonce().0[..PREFIX_BYTES].to_vec();
+    try!(writer.write_all(&prefix));
+
+    let mut frame = vec![0u8; 1 + FRAME_SIZE];
+    let mut frame_number = 0u64;
+    loop {
+        let count = try!(read_fully(reader, &mut frame[1..]));
+        // A short read means the reader is exhausted.  If the data fills the last frame exactly, an
+        // empty final frame follows it.
+        frame[0] = if count < FRAME_SIZE { FINAL_FRAME } else { MORE_FRAMES };
+        let ciphertext = crypto::box_::seal_precomputed(&frame[..1 + count], &frame_nonce(&prefix, frame_number), key);
+        let length = ciphertext.len() as u32;
+        try!(writer.write_all(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8]));
+        try!(writer.write_all(&ciphertext));
+        if frame[0] == FINAL_FRAME {
+            return Ok(());
+        }
+        frame_number += 1;
+    }
+}
+
+/// Reads a sealed stream from reader, writing the data to writer.  Data is written as each frame
+/// authenticates, so if an error is returned everything written must be discarded.
+pub fn open<R: Read, W: Write>(key: &crypto::box_::PrecomputedKey, reader: &mut R, writer: &mut W)
+        -> Result<(), ::Error> {
+    let mut prefix = [0u8; PREFIX_BYTES];
+    if try!(read_fully(reader, &mut prefix)) != PREFIX_BYTES {
+        return Err(::Error::TruncatedStream);
+    }
+
+    let mut length_bytes = [0u8; LENGTH_BYTES];
+    let mut frame_number = 0u64;
+    loop {
+        if try!(read_fully(reader, &mut length_bytes)) != LENGTH_BYTES {
+            return Err(::Error::TruncatedStream);
+        }
+        let length = length_bytes.iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        if length < 1 + crypto::box_::MACBYTES || length > 1 + FRAME_SIZE + crypto::box_::MACBYTES {
+            return Err(::Error::CorruptedData);
+        }
+        let mut ciphertext = vec![0u8; length];
+        if try!(read_fully(reader, &mut ciphertext)) != length {
+            return Err(::Error::TruncatedStream);
+        }
+        let frame = try!(crypto::box_::open_precomputed(&ciphertext, &frame_nonce(&prefix, frame_number), key)
+                         .ok_or(::Error::Decryption));
+        try!(writer.write_all(&frame[1..]));
+        match frame[0] {
+            MORE_FRAMES => frame_number += 1,
+            FINAL_FRAME => break,
+            _ => return Err(::Error::CorruptedData),
+        }
+    }
+
+    // Nothing may follow the final frame
+    if try!(read_fully(reader, &mut [0u8; 1])) != 0 {
+        return Err(::Error::CorruptedData);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::LENGTH_BYTES;
+    use super::PREFIX_BYTES;
+    use std::io::Cursor;
+    use sodiumoxide::crypto;
+    use super::super::IdType;
+    use Random;
+
+    const FULL_FRAME_BYTES: usize = LENGTH_BYTES + 1 + FRAME_SIZE + crypto::box_::MACBYTES;
+
+    fn seal_to_bytes(sender: &IdType, recipient: &IdType, data: &[u8]) -> Vec<u8> {
+        let mut sealed = Vec::new();
+        sender.seal_stream(&mut Cursor::new(data), &mut sealed, &recipient.public_keys().1).unwrap();
+        sealed
+    }
+
+    fn open_from_bytes(recipient: &IdType, sender: &IdType, sealed: &[u8]) -> Result<Vec<u8>, ::Error> {
+        let mut opened = Vec::new();
+        try!(recipient.open_stream(&mut Cursor::new(sealed), &mut opened, &sender.public_keys().1));
+        Ok(opened)
+    }
+
+    #[test]
+    fn seal_and_open() {
+        let sender = IdType::generate_random();
+        let recipient = IdType::generate_random();
+        for &size in [0, 1, FRAME_SIZE - 1, FRAME_SIZE, FRAME_SIZE + 1, 3 * FRAME_SIZE + 7].iter() {
+            let data = (0..size).map(|i| i as u8).collect::<Vec<u8>>();
+            let sealed = seal_to_bytes(&sender, &recipient, &data);
+            assert_eq!(sealed.len(), PREFIX_BYTES + (size / FRAME_SIZE + 1) *
+                                     (LENGTH_BYTES + 1 + crypto::box_::MACBYTES) + size);
+            assert_eq!(open_from_bytes(&recipient, &sender, &sealed).unwrap(), data);
+            assert!(open_from_bytes(&recipient, &IdType::generate_random(), &sealed).is_err());
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let sender = IdType::generate_random();
+        let recipient = IdType::generate_random();
+        let sealed = seal_to_bytes(&sender, &recipient, &vec![7u8; 2 * FRAME_SIZE + 100]);
+
+        // Cut at a frame boundary, so every remaining frame authenticates
+        let cut = PREFIX_BYTES + 2 * FULL_FRAME_BYTES;
+        for &length in [0, PREFIX_BYTES, cut, cut + 2, sealed.len() - 1].iter() {
+            match open_from_bytes(&recipient, &sender, &sealed[..length]) {
+                Err(::Error::TruncatedStream) => (),
+                _ => panic!("Unexpected!"),
+            }
+        }
+
+        let mut extended = sealed.clone();
+        extended.push(0);
+        match open_from_bytes(&recipient, &sender, &extended) {
+            Err(::Error::CorruptedData) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+
+    #[test]
+    fn reordered() {
+        let sender = IdType::generate_random();
+        let recipient = IdType::generate_random();
+        let sealed = seal_to_bytes(&sender, &recipient, &vec![7u8; 2 * FRAME_SIZE + 100]);
+
+        let first = PREFIX_BYTES..PREFIX_BYTES + FULL_FRAME_BYTES;
+        let second = PREFIX_BYTES + FULL_FRAME_BYTES..PREFIX_BYTES + 2 * FULL_FRAME_BYTES;
+        let mut reordered = sealed[..PREFIX_BYTES].to_vec();
+        reordered.extend(sealed[second.clone()].iter().cloned());
+        reordered.extend(sealed[first.clone()].iter().cloned());
+        reordered.extend(sealed[second.end..].iter().cloned());
+        match open_from_bytes(&recipient, &sender, &reordered) {
+            Err(::Error::Decryption) => (),
+            _ => panic!("Unexpected!"),
+        }
+
+        // Dropping a frame from the middle also changes the frame numbers
+        let mut dropped = sealed[..first.end].to_vec();
+        dropped.extend(sealed[second.end..].iter().cloned());
+        match open_from_bytes(&recipient, &sender, &dropped) {
+            Err(::Error::Decryption) => (),
+            _ => panic!("Unexpected!"),
+        }
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! JSON representation of the data types, used when they are stored on disk.
+//!
+//! Every type is written as an object whose `"tag"` member is the type's tag from `data_tags`,
+//! or the id type's own tag, so the type can be identified without knowing it in advance.  The
+//! other members mirror the fields of the type's CBOR encoding, and converting between the two is
+//! lossless.  Binary fields, including names, keys and signatures, are lowercase hex strings.
+//! Numbers are unsigned integers.  Lists of signatures are arrays of objects with `"key"` and
+//! `"signature"` members.  Members not listed are ignored when reading.
+//!
+//! ```text
+//! ImmutableData(Backup/Sacrificial)  tag, value
+//! StructuredData                     tag, type_tag, identifier, version, owners, data, max_size,
+//!                                    signatures
+//! SafeCoin                           tag, name, version, value, owners, previous_owners,
+//!                                    signatures, split
+//! SafeCoin split                     parent, parent_version, parent_value,
+//!                                    outputs (array of objects with owners, value)
+//! PublicIdType                       tag, public_sign_key, public_encrypt_key,
+//!                                    revocation_public_key, sequence_number, signature
+//! IdType                             tag, public_sign_key, public_encrypt_key, secret_sign_key,
+//!                                    secret_encrypt_key
+//! RevocationIdType                   tag, type_tags, public_key, secret_key
+//! RevocationIdTypePublicPart         tag, type_tags, public_key
+//! IdTypePublicPart                   tag, type_tag, public_sign_key, public_encrypt_key
+//! SupersededRecord                   tag, old_name, new_name, sequence_number,
+//!                                    revocation_public_key, signature
+//! RevocationCertificate              tag, revoked_name, reason, timestamp,
+//!                                    revocation_public_key, signature
+//! DataMap                            tag, chunks (array of objects with chunk_num, hash,
+//!                                    pre_hash, source_size), content
+//! Wallet                             tag, owner_name, owner, coins (array of SafeCoin objects)
+//! ```
+//!
+//! A SafeCoin's `split` is `null` for a coin which has not been split off another, and a
+//! SafeCoin's `value` is in nano units.  A RevocationCertificate's `reason` is the reason's code.
+//! The id types holding secret keys do not implement `ToJson`; they are written through
+//! `IdType::to_json_with_secret_keys` and `RevocationIdType::to_json_with_secret_key`.
+
+use helper::*;
+use routing::NameType;
+use rustc_serialize::hex::{FromHex, ToHex};
+use rustc_serialize::json::{Json, ToJson};
+use sodiumoxide::crypto;
+use std::collections::BTreeMap;
+
+/// Types which can be read back from their JSON representation
+pub trait FromJson: Sized {
+    /// Reads the value written by the type's `ToJson` implementation
+    fn from_json(json: &Json) -> Result<Self, ::Error>;
+}
+
+/// Writes value as a JSON string
+pub fn to_string<T: ToJson>(value: &T) -> String {
+    value.to_json().to_string()
+}
+
+/// Reads a value from a JSON string
+pub fn from_str<T: FromJson>(json: &str) -> Result<T, ::Error> {
+    T::from_json(&try!(parse(json)))
+}
+
+/// Parses a JSON string without interpreting it
+pub fn parse(json: &str) -> Result<Json, ::Error> {
+    Json::from_str(json).map_err(|error| ::Error::InvalidJson(format!("{}", error)))
+}
+
+/// Returns bytes as a JSON hex string
+pub fn hex(bytes: &[u8]) -> Json {
+    Json::String(bytes.to_hex())
+}
+
+/// Returns public keys as a JSON array of hex strings
+pub fn public_keys(keys: &[crypto::sign::PublicKey]) -> Json {
+    Json::Array(keys.iter().map(|key| hex(&key.0)).collect())
+}
+
+/// Returns signatures as a JSON array of objects with "key" and "signature" members
+pub fn signatures(signatures: &[(crypto::sign::PublicKey, crypto::sign::Signature)]) -> Json {
+    Json::Array(signatures.iter().map(|&(ref key, ref signature)| {
+        let mut object = BTreeMap::new();
+        let _ = object.insert("key".to_string(), hex(&key.0));
+        let _ = object.insert("signature".to_string(), hex(&signature.0));
+        Json::Object(object)
+    }).collect())
+}
+
+/// Builds a JSON object member by member
+pub struct ObjectBuilder {
+    object: BTreeMap<String, Json>,
+}
+
+impl ObjectBuilder {
+    /// Starts an object for the type with the given tag
+    pub fn new(tag: u64) -> ObjectBuilder {
+        ObjectBuilder::untagged().field("tag", tag)
+    }
+    /// Starts an object with no tag, for values nested in a tagged object
+    pub fn untagged() -> ObjectBuilder {
+        ObjectBuilder { object: BTreeMap::new() }
+    }
+    /// Adds a member
+    pub fn field<T: ToJson>(mut self, name: &str, value: T) -> ObjectBuilder {
+        let _ = self.object.insert(name.to_string(), value.to_json());
+        self
+    }
+    /// Adds a binary member as a hex string
+    pub fn bytes(self, name: &str, bytes: &[u8]) -> ObjectBuilder {
+        self.field(name, hex(bytes))
+    }
+    /// Returns the object
+    pub fn build(self) -> Json {
+        Json::Object(self.object)
+    }
+}
+
+/// Reads the members of a JSON object
+pub struct Object<'a> {
+    object: &'a BTreeMap<String, Json>,
+}
+
+impl<'a> Object<'a> {
+    /// Reads json as an object
+    pub fn new(json: &'a Json) -> Result<Object<'a>, ::Error> {
+        match json.as_object() {
+            Some(object) => Ok(Object { object: object }),
+            None => Err(::Error::InvalidJson("expected an object".to_string())),
+        }
+    }
+
+    /// Reads json as an object with any of the given tags
+    pub fn with_tag(json: &'a Json, tags: &[u64]) -> Result<Object<'a>, ::Error> {
+        let object = try!(Object::new(json));
+        if !tags.contains(&try!(object.u64("tag"))) {
+            return Err(::Error::BadTypeTag);
+        }
+        Ok(object)
+    }
+
+    /// Returns the named member
+    pub fn field(&self, name: &str) -> Result<&'a Json, ::Error> {
+        self.object.get(name).ok_or_else(|| ::Error::InvalidJson(format!("missing \"{}\"", name)))
+    }
+    /// Returns the named member as a number
+    pub fn u64(&self, name: &str) -> Result<u64, ::Error> {
+        try!(self.field(name)).as_u64()
+            .ok_or_else(|| ::Error::InvalidJson(format!("\"{}\" is not an unsigned integer", name)))
+    }
+    /// Returns the named member as an array
+    pub fn array(&self, name: &str) -> Result<&'a Vec<Json>, ::Error> {
+        try!(self.field(name)).as_array()
+            .ok_or_else(|| ::Error::InvalidJson(format!("\"{}\" is not an array", name)))
+    }
+    /// Returns the named hex member as bytes
+    pub fn bytes(&self, name: &str) -> Result<Vec<u8>, ::Error> {
+        bytes(try!(self.field(name)))
+    }
+    /// Returns the named hex member as bytes, checking their length
+    pub fn fixed_bytes(&self, name: &str, length: usize) -> Result<Vec<u8>, ::Error> {
+        let bytes = try!(self.bytes(name));
+        try!(check_length(bytes.len(), length));
+        Ok(bytes)
+    }
+    /// Returns the named hex member as a NameType
+    pub fn name_type(&self, name: &str) -> Result<NameType, ::Error> {
+        let bytes = try!(self.fixed_bytes(name, crypto::hash::sha512::HASHBYTES));
+        Ok(NameType(convert_to_array!(bytes, crypto::hash::sha512::HASHBYTES).unwrap()))
+    }
+    /// Returns the named hex member as a public signing key
+    pub fn public_key(&self, name: &str) -> Result<crypto::sign::PublicKey, ::Error> {
+        let bytes = try!(self.fixed_bytes(name, crypto::sign::PUBLICKEYBYTES));
+        Ok(crypto::sign::PublicKey(convert_to_array!(bytes, crypto::sign::PUBLICKEYBYTES).unwrap()))
+    }
+    /// Returns the named hex member as a signature
+    pub fn signature(&self, name: &str) -> Result<crypto::sign::Signature, ::Error> {
+        let bytes = try!(self.fixed_bytes(name, crypto::sign::SIGNATUREBYTES));
+        Ok(crypto::sign::Signature(convert_to_array!(bytes, crypto::sign::SIGNATUREBYTES).unwrap()))
+    }
+    /// Returns the named array of hex strings as public keys
+    pub fn public_keys(&self, name: &str) -> Result<Vec<crypto::sign::PublicKey>, ::Error> {
+        let mut key_vecs = Vec::new();
+        for key in try!(self.array(name)) {
+            key_vecs.push(try!(bytes(key)));
+        }
+        vecs_to_public_keys(key_vecs)
+    }
+    /// Returns the named array of signature objects
+    pub fn signatures(&self, name: &str)
+            -> Result<Vec<(crypto::sign::PublicKey, crypto::sign::Signature)>, ::Error> {
+        let mut key_vecs = Vec::new();
+        let mut signature_vecs = Vec::new();
+        for signature in try!(self.array(name)) {
+            let signature = try!(Object::new(signature));
+            key_vecs.push(try!(signature.bytes("key")));
+            signature_vecs.push(try!(signature.bytes("signature")));
+        }
+        let keys = try!(vecs_to_public_keys(key_vecs));
+        let signatures = try!(vecs_to_signatures(signature_vecs));
+        Ok(keys.into_iter().zip(signatures.into_iter()).collect())
+    }
+}
+
+/// Reads a JSON hex string as bytes
+pub fn bytes(json: &Json) -> Result<Vec<u8>, ::Error> {
+    match json.as_string() {
+        Some(string) => string.from_hex().map_err(|_| ::Error::InvalidJson(format!("invalid hex \"{}\"", string))),
+        None => Err(::Error::InvalidJson("expected a hex string".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use id::IdType;
+    use rustc_serialize::json::Json;
+    use Random;
+
+    #[test]
+    fn read_members() {
+        let key = IdType::generate_random().public_keys().0;
+        let signature = IdType::generate_random().sign_detached(b"data");
+        let json = ObjectBuilder::new(7).field("number", 42u64)
+                                        .bytes("bytes", &[0, 1, 254, 255])
+                                        .field("keys", public_keys(&[key]))
+                                        .field("signatures", signatures(&[(key, signature)]))
+                                        .build();
+        let json = parse(&json.to_string()).unwrap();
+        assert_eq!(json.find("bytes"), Some(&Json::String("0001feff".to_string())));
+
+        let object = Object::with_tag(&json, &[6, 7]).unwrap();
+        assert_eq!(object.u64("number").unwrap(), 42);
+        assert_eq!(object.bytes("bytes").unwrap(), vec![0, 1, 254, 255]);
+        assert_eq!(object.public_keys("keys").unwrap()[0].0, key.0);
+        let read = object.signatures("signatures").unwrap();
+        assert_eq!((read[0].1).0.to_vec(), signature.0.to_vec());
+
+        match Object::with_tag(&json, &[8]) {
+            Err(::Error::BadTypeTag) => (),
+            _ => panic!("Unexpected!"),
+        }
+        assert!(object.u64("missing").is_err());
+        assert!(object.u64("bytes").is_err());
+        assert!(object.fixed_bytes("bytes", 5).is_err());
+        assert!(object.public_key("bytes").is_err());
+        assert!(object.signature("bytes").is_err());
+        assert!(parse("{\"tag\": ").is_err());
+    }
+}
@@ -37,7 +37,7 @@
 //! This library implements the fundimental data types used on the SAFE Network
 //! The serialisation mechnism used is ``cbor``` which is an IETF Rfc [7049](http://tools.ietf.org/html/rfc7049)
 //! for serialising data and is an attempt to upgrade messagepack and ASN.1
-//! On disk serialisation is [JSON](https://www.ietf.org/rfc/rfc4627.txt)
+//! On disk serialisation is [JSON](https://www.ietf.org/rfc/rfc4627.txt), described in the `json` module
 //!
 //! [Project github page](https://github.com/maidsafe/maidsafe_types)
 
@@ -51,13 +51,28 @@ extern crate routing;
 pub mod helper;
 /// Holds the structs for Id related Types such as Maid, AnMaid, Mpid, etc
 pub mod id;
-/// Holds the structs related to data such as ImmutableData/Backup/Sacrificial and StructuredData
+/// Holds the structs related to data such as ImmutableData/Backup/Sacrificial, StructuredData and
+/// the self-encryption DataMap
 pub mod data;
 /// SafeCoin related details
 pub mod coin;
-
-pub use id::{RevocationIdType, IdType, PublicIdType};
-pub use data::{ImmutableData, ImmutableDataBackup, ImmutableDataSacrificial, StructuredData};
+/// Error types returned by the library
+pub mod error;
+/// Decoder for any of the types tagged in data_tags
+pub mod any_data;
+/// JSON representation of the data types for storing on disk
+pub mod json;
+/// Versioned envelope for the encodings of the data types
+pub mod envelope;
+/// Canonical encoding of the content covered by signatures
+pub mod signable;
+
+pub use id::{RevocationIdType, RevocationIdTypePublicPart, IdType, IdTypePublicPart, PublicIdType};
+pub use data::{ImmutableData, ImmutableDataBackup, ImmutableDataSacrificial, StructuredData, DataMap};
+pub use coin::SafeCoin;
+pub use any_data::AnyData;
+pub use error::Error;
+pub use signable::Signable;
 
 /// TypeTag trait
 pub trait TypeTag {
@@ -105,15 +120,12 @@ pub trait Random {
     /// Generates a random instance and returns the created random instance
     fn generate_random() -> Self;
 }
-/// Crypto Error types
-pub enum CryptoError {
-    /// Unknown Error Type
-    Unknown
-}
-
 /// All Maidsafe tagging should offset from this
 pub const MAIDSAFE_TAG: u64 = 5483_000;
 
+/// Number of tags from MAIDSAFE_TAG onwards which are reserved for Maidsafe types
+pub const MAIDSAFE_TAG_RANGE: u64 = 1000;
+
 /// All Maidsafe Data tags
 #[allow(missing_docs)]
 pub mod data_tags {
@@ -129,6 +141,12 @@ pub mod data_tags {
     pub const MPID_TAG: u64                       = MAIDSAFE_DATA_TAG + 8;
     pub const PUBLIC_MAID_TAG: u64                = MAIDSAFE_DATA_TAG + 9;
     pub const PUBLIC_MPID_TAG: u64                = MAIDSAFE_DATA_TAG + 10;
+    pub const SAFECOIN_TAG: u64                   = MAIDSAFE_DATA_TAG + 11;
+    pub const DATA_MAP_TAG: u64                   = MAIDSAFE_DATA_TAG + 12;
+    pub const SUPERSEDED_ID_TAG: u64              = MAIDSAFE_DATA_TAG + 13;
+    pub const REVOCATION_CERTIFICATE_TAG: u64     = MAIDSAFE_DATA_TAG + 14;
+    pub const ID_PUBLIC_PART_TAG: u64             = MAIDSAFE_DATA_TAG + 15;
+    pub const WALLET_TAG: u64                     = MAIDSAFE_DATA_TAG + 16;
+    pub const ENVELOPE_TAG: u64                   = MAIDSAFE_DATA_TAG + 17;
+    pub const REVOCATION_ID_PUBLIC_PART_TAG: u64  = MAIDSAFE_DATA_TAG + 18;
 }
-
-mod test_utils;
@@ -0,0 +1,123 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Canonical encoding of signed content.
+//!
+//! Signatures are made over `Signable::signable_bytes`, which writes the signed fields of a type
+//! in a fixed order, each in one of three forms:
+//!
+//! ```text
+//! number  8 bytes, big-endian
+//! bytes   length as a number, then the bytes
+//! list    count as a number, then each item
+//! ```
+//!
+//! The first field is always the name of the content as bytes, for instance `PublicIdType`, so a
+//! signature over one kind of content cannot be passed off as a signature over another.  Keys,
+//! names and signatures are written as bytes and type tags as numbers.  The fields signed by each
+//! type are listed on its `Signable` implementation, and its tests give the encoding of an example
+//! with fixed keys as hex.
+
+use sodiumoxide::crypto;
+
+/// Content which is signed
+pub trait Signable {
+    /// Returns the canonical bytes which the signatures over the content are made over
+    fn signable_bytes(&self) -> Vec<u8>;
+}
+
+/// Writes fields in the canonical encoding
+pub struct SignableBytes {
+    bytes: Vec<u8>,
+}
+
+impl SignableBytes {
+    /// Starts the encoding of the named kind of content
+    pub fn new(content: &str) -> SignableBytes {
+        SignableBytes { bytes: Vec::new() }.bytes(content.as_bytes())
+    }
+    /// Writes a number
+    pub fn u64(mut self, value: u64) -> SignableBytes {
+        for i in 0..8 {
+            self.bytes.push((value >> (56 - 8 * i)) as u8);
+        }
+        self
+    }
+    /// Writes bytes, preceded by their length
+    pub fn bytes(self, bytes: &[u8]) -> SignableBytes {
+        let mut written = self.u64(bytes.len() as u64);
+        written.bytes.extend(bytes.iter().cloned());
+        written
+    }
+    /// Writes a list of public keys, preceded by their count
+    pub fn public_keys(self, keys: &[crypto::sign::PublicKey]) -> SignableBytes {
+        keys.iter().fold(self.u64(keys.len() as u64), |written, key| written.bytes(&key.0))
+    }
+    /// Returns the encoding
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// The canonical encoding written out by hand, independently of `SignableBytes`, for checking each
+/// `Signable` implementation
+#[cfg(test)]
+pub mod expected {
+    /// Returns a number field
+    pub fn number(value: u64) -> Vec<u8> {
+        (0..8).map(|i| (value >> (56 - 8 * i)) as u8).collect()
+    }
+    /// Returns a bytes field, preceded by the full length of bytes
+    pub fn field(bytes: &[u8]) -> Vec<u8> {
+        let mut field = number(bytes.len() as u64);
+        field.extend(bytes.iter().cloned());
+        field
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::expected::{field, number};
+    use sodiumoxide::crypto;
+
+    #[test]
+    fn layout() {
+        let key = crypto::sign::PublicKey([9u8; crypto::sign::PUBLICKEYBYTES]);
+        let bytes = SignableBytes::new("Test").u64(0x0102030405060708)
+                                              .bytes(&[])
+                                              .public_keys(&[key, key])
+                                              .into_bytes();
+        let mut expected = vec![0u8, 0, 0, 0, 0, 0, 0, 4];
+        expected.extend(b"Test".iter().cloned());
+        expected.extend([1u8, 2, 3, 4, 5, 6, 7, 8].iter().cloned());
+        expected.extend([0u8; 8].iter().cloned());
+        expected.extend([0u8, 0, 0, 0, 0, 0, 0, 2].iter().cloned());
+        for _ in 0..2 {
+            expected.extend([0u8, 0, 0, 0, 0, 0, 0, 32].iter().cloned());
+            expected.extend([9u8; 32].iter().cloned());
+        }
+        assert_eq!(bytes, expected);
+
+        assert_eq!(field(&[0u8; 300])[..8].to_vec(), number(300));
+        assert_eq!(bytes[..12].to_vec(), field(b"Test"));
+
+        // Length prefixes keep the boundary between fields
+        assert!(SignableBytes::new("ab").bytes(b"c").into_bytes() !=
+                SignableBytes::new("a").bytes(b"bc").into_bytes());
+    }
+}